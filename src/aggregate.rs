@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// How an aggregate registered with [`MapOptions::with_aggregate`](crate::MapOptions::with_aggregate)
+/// combines the values it is projected from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    /// The sum of every projection, wrapping on overflow
+    Sum,
+    /// The number of values with a projection
+    Count,
+    /// The smallest projection, or `None` if there are none
+    Min,
+    /// The largest projection, or `None` if there are none
+    Max,
+}
+
+/// The aggregates registered on a map. One per copy, see [`Inner`](crate::inner::Inner).
+pub(crate) struct Aggregates<Key, MutV, RefV> {
+    aggregates: Vec<(&'static str, Aggregator<Key, MutV, RefV>)>,
+}
+
+/// Projects an entry to the value it adds to an aggregate, if any
+type Project<Key, MutV, RefV> = Arc<dyn Fn(&Key, &RefV, &MutV) -> Option<i64> + Send + Sync>;
+
+struct Aggregator<Key, MutV, RefV> {
+    kind: Aggregate,
+    project: Project<Key, MutV, RefV>,
+    sum: i64,
+    count: u64,
+    /// Every projection, with how many values it was projected from, for `Min` and `Max`
+    projections: BTreeMap<i64, u64>,
+}
+
+impl<Key, MutV, RefV> Aggregator<Key, MutV, RefV> {
+    fn add(&mut self, projection: i64) {
+        match self.kind {
+            Aggregate::Sum => self.sum = self.sum.wrapping_add(projection),
+            Aggregate::Count => self.count += 1,
+            Aggregate::Min | Aggregate::Max => {
+                *self.projections.entry(projection).or_default() += 1;
+            }
+        }
+    }
+
+    fn subtract(&mut self, projection: i64) {
+        match self.kind {
+            Aggregate::Sum => self.sum = self.sum.wrapping_sub(projection),
+            Aggregate::Count => self.count -= 1,
+            Aggregate::Min | Aggregate::Max => {
+                if let Some(count) = self.projections.get_mut(&projection) {
+                    *count -= 1;
+                    if *count == 0 {
+                        self.projections.remove(&projection);
+                    }
+                }
+            }
+        }
+    }
+
+    fn value(&self) -> Option<i64> {
+        match self.kind {
+            Aggregate::Sum => Some(self.sum),
+            Aggregate::Count => Some(self.count as i64),
+            Aggregate::Min => self.projections.first_key_value().map(|(min, _)| *min),
+            Aggregate::Max => self.projections.last_key_value().map(|(max, _)| *max),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.sum = 0;
+        self.count = 0;
+        self.projections.clear();
+    }
+}
+
+impl<Key, MutV, RefV> Aggregates<Key, MutV, RefV> {
+    pub(crate) fn new() -> Self {
+        Aggregates {
+            aggregates: Vec::new(),
+        }
+    }
+
+    pub(crate) fn register<F>(&mut self, name: &'static str, kind: Aggregate, project: F)
+    where
+        F: Fn(&Key, &RefV, &MutV) -> Option<i64> + Send + Sync + 'static,
+    {
+        assert!(
+            self.aggregates.iter().all(|(n, _)| *n != name),
+            "an aggregate named {name:?} is already registered"
+        );
+        let aggregator = Aggregator {
+            kind,
+            project: Arc::new(project),
+            sum: 0,
+            count: 0,
+            projections: BTreeMap::new(),
+        };
+        self.aggregates.push((name, aggregator));
+    }
+
+    /// Fold a value which has entered the map into every aggregate.
+    pub(crate) fn insert(&mut self, key: &Key, ref_v: &RefV, mut_v: &MutV) {
+        for (_, aggregator) in &mut self.aggregates {
+            if let Some(projection) = (aggregator.project)(key, ref_v, mut_v) {
+                aggregator.add(projection);
+            }
+        }
+    }
+
+    /// Take a value which is leaving the map, or about to be mutated, out of every aggregate.
+    pub(crate) fn remove(&mut self, key: &Key, ref_v: &RefV, mut_v: &MutV) {
+        for (_, aggregator) in &mut self.aggregates {
+            if let Some(projection) = (aggregator.project)(key, ref_v, mut_v) {
+                aggregator.subtract(projection);
+            }
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        for (_, aggregator) in &mut self.aggregates {
+            aggregator.clear();
+        }
+    }
+
+    /// The current value of the aggregate called `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no aggregate called `name`.
+    pub(crate) fn get(&self, name: &str) -> Option<i64> {
+        let (_, aggregator) = self
+            .aggregates
+            .iter()
+            .find(|(n, _)| *n == name)
+            .unwrap_or_else(|| panic!("no aggregate named {name:?} is registered"));
+        aggregator.value()
+    }
+}
+
+impl<Key, MutV, RefV> Clone for Aggregates<Key, MutV, RefV> {
+    /// Clone the registered aggregates, as they are over an empty map.
+    fn clone(&self) -> Self {
+        Aggregates {
+            aggregates: self
+                .aggregates
+                .iter()
+                .map(|(name, aggregator)| {
+                    let aggregator = Aggregator {
+                        kind: aggregator.kind,
+                        project: Arc::clone(&aggregator.project),
+                        sum: 0,
+                        count: 0,
+                        projections: BTreeMap::new(),
+                    };
+                    (*name, aggregator)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<Key, MutV, RefV> fmt::Debug for Aggregates<Key, MutV, RefV> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.aggregates
+                    .iter()
+                    .map(|(name, aggregator)| (name, aggregator.kind)),
+            )
+            .finish()
+    }
+}
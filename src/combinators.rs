@@ -0,0 +1,116 @@
+//! Operations which build operations on compound values out of operations on their parts.
+
+use std::fmt;
+
+use crate::mutable::Mutable;
+
+/// An operation on one half of a pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Either<L, R> {
+    /// Mutate the first element of the pair
+    Left(L),
+    /// Mutate the second element of the pair
+    Right(R),
+}
+
+impl<A, B, OA, OB> Mutable<Either<OA, OB>> for (A, B)
+where
+    A: Mutable<OA>,
+    B: Mutable<OB>,
+{
+    fn mutate_first(&mut self, operation: &mut Either<OA, OB>) {
+        match operation {
+            Either::Left(operation) => self.0.mutate_first(operation),
+            Either::Right(operation) => self.1.mutate_first(operation),
+        }
+    }
+
+    fn mutate_second(&mut self, operation: Either<OA, OB>) {
+        match operation {
+            Either::Left(operation) => self.0.mutate_second(operation),
+            Either::Right(operation) => self.1.mutate_second(operation),
+        }
+    }
+}
+
+/// Several operations, applied in order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Seq<O>(pub Vec<O>);
+
+impl<T, O> Mutable<Seq<O>> for T
+where
+    T: Mutable<O>,
+{
+    fn mutate_first(&mut self, operation: &mut Seq<O>) {
+        for operation in &mut operation.0 {
+            self.mutate_first(operation);
+        }
+    }
+
+    fn mutate_second(&mut self, operation: Seq<O>) {
+        for operation in operation.0 {
+            self.mutate_second(operation);
+        }
+    }
+}
+
+/// An operation on a part of a value, such as one field of a struct, which `field` projects out
+/// of it.
+///
+/// ```
+/// use sevmap::muts::{Counter, Field, Mutable};
+///
+/// #[derive(Clone)]
+/// struct Stats {
+///     hits: u64,
+///     misses: u64,
+/// }
+///
+/// let mut stats = Stats { hits: 0, misses: 0 };
+/// let mut op = Field::new(|stats: &mut Stats| &mut stats.hits, Counter::Add(1));
+/// stats.mutate_first(&mut op);
+/// assert_eq!(stats.hits, 1);
+/// ```
+///
+/// `field` is called on both copies of the map, and must always project out the same part.
+#[derive(Clone, Copy)]
+pub struct Field<F, O> {
+    field: F,
+    operation: O,
+}
+
+impl<F, O> Field<F, O> {
+    pub fn new<T, V>(field: F, operation: O) -> Self
+    where
+        F: Fn(&mut T) -> &mut V,
+        V: Mutable<O>,
+    {
+        Field { field, operation }
+    }
+}
+
+impl<F, O> fmt::Debug for Field<F, O>
+where
+    O: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Field")
+            .field("operation", &self.operation)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, V, F, O> Mutable<Field<F, O>> for T
+where
+    T: Clone,
+    F: Fn(&mut T) -> &mut V,
+    V: Mutable<O>,
+{
+    fn mutate_first(&mut self, operation: &mut Field<F, O>) {
+        (operation.field)(self).mutate_first(&mut operation.operation);
+    }
+
+    fn mutate_second(&mut self, operation: Field<F, O>) {
+        (operation.field)(self).mutate_second(operation.operation);
+    }
+}
@@ -0,0 +1,354 @@
+//! Conflict-free replicated data types, for use as the mutable part of a value.
+//!
+//! Each type records which replica made each change, identified by a replica id `R`, so that
+//! the state of another replica can be folded in with a `Merge` operation. Merging is
+//! commutative, associative and idempotent: however many times and in whatever order replicas
+//! merge each other's states, they converge on the same state.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::mutable::Mutable;
+
+/// A state which can fold in the state of another replica
+pub trait Merge {
+    /// Fold `other` into this state.
+    ///
+    /// Must be commutative, associative and idempotent, as well as deterministic.
+    fn merge(&mut self, other: &Self);
+}
+
+/// A grow-only counter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GCounter<R: Ord> {
+    counts: BTreeMap<R, u64>,
+}
+
+/// An operation on a [`GCounter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GCounterOp<R: Ord> {
+    /// Add to the count of the given replica
+    Increment(R, u64),
+    Merge(GCounter<R>),
+}
+
+impl<R: Ord> Default for GCounter<R> {
+    fn default() -> Self {
+        GCounter {
+            counts: BTreeMap::new(),
+        }
+    }
+}
+
+impl<R: Ord> GCounter<R> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The total of every replica's count, saturating at `u64::MAX`
+    pub fn value(&self) -> u64 {
+        self.counts
+            .values()
+            .fold(0, |total, count| total.saturating_add(*count))
+    }
+
+    /// Add `by` to the count of `replica`, saturating at `u64::MAX`.
+    ///
+    /// A saturated count stays at `u64::MAX` rather than wrapping back to a lower count, which
+    /// merging would otherwise discard in favour of the higher one.
+    pub fn increment(&mut self, replica: R, by: u64) {
+        let count = self.counts.entry(replica).or_default();
+        *count = count.saturating_add(by);
+    }
+}
+
+impl<R: Ord + Clone> Merge for GCounter<R> {
+    fn merge(&mut self, other: &Self) {
+        for (replica, count) in &other.counts {
+            let own = self.counts.entry(replica.clone()).or_default();
+            *own = (*own).max(*count);
+        }
+    }
+}
+
+impl<R: Ord + Clone> Mutable<GCounterOp<R>> for GCounter<R> {
+    fn mutate_first(&mut self, operation: &mut GCounterOp<R>) {
+        match *operation {
+            GCounterOp::Increment(ref replica, by) => self.increment(replica.clone(), by),
+            GCounterOp::Merge(ref other) => self.merge(other),
+        }
+    }
+
+    fn mutate_second(&mut self, operation: GCounterOp<R>) {
+        match operation {
+            GCounterOp::Increment(replica, by) => self.increment(replica, by),
+            GCounterOp::Merge(other) => self.merge(&other),
+        }
+    }
+}
+
+/// A counter which can be both incremented and decremented
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PNCounter<R: Ord> {
+    increments: GCounter<R>,
+    decrements: GCounter<R>,
+}
+
+/// An operation on a [`PNCounter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PNCounterOp<R: Ord> {
+    Increment(R, u64),
+    Decrement(R, u64),
+    Merge(PNCounter<R>),
+}
+
+impl<R: Ord> Default for PNCounter<R> {
+    fn default() -> Self {
+        PNCounter {
+            increments: GCounter::new(),
+            decrements: GCounter::new(),
+        }
+    }
+}
+
+impl<R: Ord> PNCounter<R> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The total of every replica's increments, less the total of their decrements, saturating
+    /// at `i64::MIN` and `i64::MAX`
+    pub fn value(&self) -> i64 {
+        let value = i128::from(self.increments.value()) - i128::from(self.decrements.value());
+        value.clamp(i64::MIN.into(), i64::MAX.into()) as i64
+    }
+
+    pub fn increment(&mut self, replica: R, by: u64) {
+        self.increments.increment(replica, by);
+    }
+
+    pub fn decrement(&mut self, replica: R, by: u64) {
+        self.decrements.increment(replica, by);
+    }
+}
+
+impl<R: Ord + Clone> Merge for PNCounter<R> {
+    fn merge(&mut self, other: &Self) {
+        self.increments.merge(&other.increments);
+        self.decrements.merge(&other.decrements);
+    }
+}
+
+impl<R: Ord + Clone> Mutable<PNCounterOp<R>> for PNCounter<R> {
+    fn mutate_first(&mut self, operation: &mut PNCounterOp<R>) {
+        match *operation {
+            PNCounterOp::Increment(ref replica, by) => self.increment(replica.clone(), by),
+            PNCounterOp::Decrement(ref replica, by) => self.decrement(replica.clone(), by),
+            PNCounterOp::Merge(ref other) => self.merge(other),
+        }
+    }
+
+    fn mutate_second(&mut self, operation: PNCounterOp<R>) {
+        match operation {
+            PNCounterOp::Increment(replica, by) => self.increment(replica, by),
+            PNCounterOp::Decrement(replica, by) => self.decrement(replica, by),
+            PNCounterOp::Merge(other) => self.merge(&other),
+        }
+    }
+}
+
+/// A last-writer-wins register.
+///
+/// Every write is stamped with a timestamp, and the write with the greatest timestamp wins.
+/// Writes with equal timestamps are ordered by replica id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LwwRegister<T, R> {
+    value: T,
+    timestamp: u64,
+    replica: R,
+}
+
+/// An operation on an [`LwwRegister`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LwwRegisterOp<T, R> {
+    /// Write a value, if the timestamp and replica id order after the current write
+    Set(T, u64, R),
+    Merge(LwwRegister<T, R>),
+}
+
+impl<T, R: Ord> LwwRegister<T, R> {
+    pub fn new(value: T, timestamp: u64, replica: R) -> Self {
+        LwwRegister {
+            value,
+            timestamp,
+            replica,
+        }
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// The timestamp of the winning write
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Whether a write stamped with `timestamp` by `replica` would win over the current one
+    fn wins(&self, timestamp: u64, replica: &R) -> bool {
+        (timestamp, replica) > (self.timestamp, &self.replica)
+    }
+
+    pub fn set(&mut self, value: T, timestamp: u64, replica: R) {
+        if self.wins(timestamp, &replica) {
+            *self = LwwRegister::new(value, timestamp, replica);
+        }
+    }
+}
+
+impl<T: Clone, R: Ord + Clone> Merge for LwwRegister<T, R> {
+    fn merge(&mut self, other: &Self) {
+        if self.wins(other.timestamp, &other.replica) {
+            self.clone_from(other);
+        }
+    }
+}
+
+impl<T: Clone, R: Ord + Clone> Mutable<LwwRegisterOp<T, R>> for LwwRegister<T, R> {
+    fn mutate_first(&mut self, operation: &mut LwwRegisterOp<T, R>) {
+        match *operation {
+            LwwRegisterOp::Set(ref value, timestamp, ref replica) => {
+                if self.wins(timestamp, replica) {
+                    *self = LwwRegister::new(value.clone(), timestamp, replica.clone());
+                }
+            }
+            LwwRegisterOp::Merge(ref other) => self.merge(other),
+        }
+    }
+
+    fn mutate_second(&mut self, operation: LwwRegisterOp<T, R>) {
+        match operation {
+            LwwRegisterOp::Set(value, timestamp, replica) => self.set(value, timestamp, replica),
+            LwwRegisterOp::Merge(other) => {
+                if self.wins(other.timestamp, &other.replica) {
+                    *self = other;
+                }
+            }
+        }
+    }
+}
+
+/// An observed-remove set, in which an add wins over a concurrent remove.
+///
+/// Every add is tagged with its replica id and that replica's count of adds so far, and a remove
+/// only removes the adds it has observed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrSet<T: Ord, R: Ord> {
+    /// The tags of the adds of each element which have not been removed
+    elements: BTreeMap<T, BTreeSet<(R, u64)>>,
+    /// The tags of every add which has been removed
+    removed: BTreeSet<(R, u64)>,
+    /// The number of adds made by each replica
+    clock: BTreeMap<R, u64>,
+}
+
+/// An operation on an [`OrSet`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrSetOp<T: Ord, R: Ord> {
+    /// Add an element on behalf of the given replica, which must be the replica making the add
+    Add(T, R),
+    /// Remove every add of an element observed so far
+    Remove(T),
+    Merge(OrSet<T, R>),
+}
+
+impl<T: Ord, R: Ord> Default for OrSet<T, R> {
+    fn default() -> Self {
+        OrSet {
+            elements: BTreeMap::new(),
+            removed: BTreeSet::new(),
+            clock: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: Ord, R: Ord + Clone> OrSet<T, R> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&self, element: &T) -> bool {
+        self.elements.contains_key(element)
+    }
+
+    /// Iterate over the elements of the set, in order
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.elements.keys()
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Add `element` on behalf of `replica`.
+    ///
+    /// Does nothing once `replica` has made `u64::MAX` adds, as seen locally or through a merge,
+    /// since the tag of another add would no longer be unique.
+    pub fn add(&mut self, element: T, replica: R) {
+        let count = self.clock.entry(replica.clone()).or_default();
+        let Some(next) = count.checked_add(1) else {
+            return;
+        };
+        *count = next;
+        let tag = (replica, *count);
+        self.elements.entry(element).or_default().insert(tag);
+    }
+
+    pub fn remove(&mut self, element: &T) {
+        if let Some(tags) = self.elements.remove(element) {
+            self.removed.extend(tags);
+        }
+    }
+}
+
+impl<T: Ord + Clone, R: Ord + Clone> Merge for OrSet<T, R> {
+    fn merge(&mut self, other: &Self) {
+        self.removed.extend(other.removed.iter().cloned());
+        for (element, tags) in &other.elements {
+            self.elements
+                .entry(element.clone())
+                .or_default()
+                .extend(tags.iter().cloned());
+        }
+        let removed = &self.removed;
+        self.elements.retain(|_, tags| {
+            tags.retain(|tag| !removed.contains(tag));
+            !tags.is_empty()
+        });
+        for (replica, count) in &other.clock {
+            let own = self.clock.entry(replica.clone()).or_default();
+            *own = (*own).max(*count);
+        }
+    }
+}
+
+impl<T: Ord + Clone, R: Ord + Clone> Mutable<OrSetOp<T, R>> for OrSet<T, R> {
+    fn mutate_first(&mut self, operation: &mut OrSetOp<T, R>) {
+        match *operation {
+            OrSetOp::Add(ref element, ref replica) => self.add(element.clone(), replica.clone()),
+            OrSetOp::Remove(ref element) => self.remove(element),
+            OrSetOp::Merge(ref other) => self.merge(other),
+        }
+    }
+
+    fn mutate_second(&mut self, operation: OrSetOp<T, R>) {
+        match operation {
+            OrSetOp::Add(element, replica) => self.add(element, replica),
+            OrSetOp::Remove(element) => self.remove(&element),
+            OrSetOp::Merge(other) => self.merge(&other),
+        }
+    }
+}
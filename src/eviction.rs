@@ -0,0 +1,89 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+/// How to choose which value to evict once the map holds more than its maximum number of
+/// entries, see [`MapOptions::with_max_entries`](crate::MapOptions::with_max_entries).
+#[derive(Debug)]
+pub enum EvictionPolicy<MutV> {
+    /// Evict the value which was inserted or mutated least recently
+    Lru,
+    /// Evict the value which was inserted least recently
+    Fifo,
+    /// Evict the value with the lowest mutable state according to the comparator, breaking ties
+    /// by evicting the value which was inserted least recently.
+    ///
+    /// Note that finding the lowest value scans the whole map, for every value evicted.
+    LowestMutV(fn(&MutV, &MutV) -> Ordering),
+    /// Evict the value which readers have looked up least recently, breaking ties by evicting
+    /// the value which was inserted least recently. Implies
+    /// [`Options::with_access_tracking`](crate::Options::with_access_tracking).
+    ///
+    /// Note that finding this value scans the whole map, for every value evicted.
+    LeastRecentlyRead,
+}
+
+impl<MutV> Clone for EvictionPolicy<MutV> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<MutV> Copy for EvictionPolicy<MutV> {}
+
+pub(crate) struct Eviction<Key, MutV> {
+    pub(crate) max_entries: usize,
+    pub(crate) policy: EvictionPolicy<MutV>,
+    /// Keys by the stamp of their value, least recent first. Unused by `LowestMutV`.
+    pub(crate) order: BTreeMap<u64, Key>,
+    /// Keys evicted by `absorb_first`, shared with the write handle to report from `publish`
+    pub(crate) evicted: Arc<Mutex<Vec<Key>>>,
+    /// Victims chosen by `absorb_first` which `absorb_second` has yet to evict, in order.
+    ///
+    /// Used by policies whose choice depends on state outside of the map, which could change
+    /// between the two absorbs; shared between both copies.
+    pub(crate) decided: Arc<Mutex<VecDeque<Key>>>,
+}
+
+impl<Key, MutV> Eviction<Key, MutV> {
+    pub(crate) fn new(
+        max_entries: usize,
+        policy: EvictionPolicy<MutV>,
+        evicted: Arc<Mutex<Vec<Key>>>,
+    ) -> Self {
+        Eviction {
+            max_entries,
+            policy,
+            order: BTreeMap::new(),
+            evicted,
+            decided: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// Whether the stamp of a value moves forward when it is mutated
+    pub(crate) fn stamps_writes(&self) -> bool {
+        matches!(self.policy, EvictionPolicy::Lru)
+    }
+
+    /// Whether `order` is used to choose victims
+    pub(crate) fn is_ordered(&self) -> bool {
+        matches!(self.policy, EvictionPolicy::Lru | EvictionPolicy::Fifo)
+    }
+}
+
+impl<Key, MutV> Clone for Eviction<Key, MutV>
+where
+    Key: Clone,
+{
+    fn clone(&self) -> Self {
+        Eviction {
+            max_entries: self.max_entries,
+            policy: self.policy,
+            order: self.order.clone(),
+            evicted: Arc::clone(&self.evicted),
+            decided: Arc::clone(&self.decided),
+        }
+    }
+}
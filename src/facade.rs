@@ -0,0 +1,446 @@
+//! The handles shared by the [`immutable`](crate::immutable) and
+//! [`mutable_only`](crate::mutable_only) maps.
+//!
+//! Both are thin facades over a map which keeps each value entirely in one of its two parts,
+//! with `()` in the other, and hide the split between them. A [`Layout`] says which part that
+//! is; use the aliases in each module rather than naming these types directly.
+
+use std::borrow::Borrow;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::time::{Duration, Instant};
+
+use left_right::ReadGuard;
+
+use crate::{
+    MapOptions, handles, handles::Outcome, inner::Value, mutable::Mutable, read_ref,
+    stable_hash_eq::StableHashEq,
+};
+
+/// Which part of the underlying map a facade keeps its values in.
+///
+/// _This trait is sealed and cannot be implemented outside of this crate._
+pub trait Layout<V>: sealed_layout::Sealed {
+    type MutV: Clone;
+    type RefV;
+
+    /// Split a value into the two parts of the underlying map
+    fn split(v: V) -> (Self::RefV, Self::MutV);
+
+    /// Join the two parts of a value taken out of the underlying map back into a value
+    fn join(ref_v: Self::RefV, mut_v: Self::MutV) -> V;
+
+    /// The part of a value in the underlying map which holds the facade's value
+    fn part(value: &Value<Self::MutV, Self::RefV, crate::aliasing::NoDrop>) -> &V;
+}
+
+mod sealed_layout {
+    pub trait Sealed {}
+}
+
+/// Values are kept in the immutable part, see [`immutable`](crate::immutable).
+#[derive(Debug)]
+pub enum Immutable {}
+
+/// Values are kept in the mutable part, see [`mutable_only`](crate::mutable_only).
+#[derive(Debug)]
+pub enum MutableOnly {}
+
+impl sealed_layout::Sealed for Immutable {}
+impl sealed_layout::Sealed for MutableOnly {}
+
+impl<V> Layout<V> for Immutable {
+    type MutV = ();
+    type RefV = V;
+
+    fn split(v: V) -> (V, ()) {
+        (v, ())
+    }
+
+    fn join(ref_v: V, _: ()) -> V {
+        ref_v
+    }
+
+    fn part(value: &Value<(), V, crate::aliasing::NoDrop>) -> &V {
+        value.ref_v()
+    }
+}
+
+impl<V: Clone> Layout<V> for MutableOnly {
+    type MutV = V;
+    type RefV = ();
+
+    fn split(v: V) -> ((), V) {
+        ((), v)
+    }
+
+    fn join(_: (), mut_v: V) -> V {
+        mut_v
+    }
+
+    fn part(value: &Value<V, (), crate::aliasing::NoDrop>) -> &V {
+        value.mut_v()
+    }
+}
+
+/// A write handle to a facade map, see the [module documentation](self).
+pub struct WriteHandle<L, Key, V, Meta, Op>
+where
+    L: Layout<V>,
+    L::MutV: Mutable<Op>,
+    Key: Eq + Hash + Clone,
+    Meta: Clone,
+{
+    handle: handles::WriteHandle<Key, L::MutV, L::RefV, Meta, Op>,
+    read: ReadHandle<L, Key, V, Meta>,
+}
+
+/// A read handle to a facade map, see the [module documentation](self).
+pub struct ReadHandle<L, Key, V, Meta>
+where
+    L: Layout<V>,
+    Key: Eq + Hash + Clone,
+    Meta: Clone,
+{
+    handle: handles::ReadHandle<Key, L::MutV, L::RefV, Meta>,
+    layout: PhantomData<L>,
+}
+
+/// A guard on one published version of a facade map, see [`ReadHandle::enter`].
+pub struct MapReadRef<'rh, L, Key, V, Meta>
+where
+    L: Layout<V>,
+    Key: Eq + Hash,
+    Meta: Clone,
+{
+    map: read_ref::MapReadRef<'rh, Key, L::MutV, L::RefV, Meta>,
+    layout: PhantomData<L>,
+}
+
+/// The value handed back by [`WriteHandle::remove_and_take`], which resolves like an
+/// [`Outcome`] once the value has been removed from both copies of the map.
+pub struct Removed<L, V>
+where
+    L: Layout<V>,
+{
+    outcome: Outcome<Option<(L::RefV, L::MutV)>>,
+}
+
+impl<L, V> Removed<L, V>
+where
+    L: Layout<V>,
+{
+    /// Whether the removal has been applied to both copies of the map yet
+    pub fn is_resolved(&self) -> bool {
+        self.outcome.is_resolved()
+    }
+
+    /// Take the removed value out, see [`Outcome::take`]. The inner `None` means there was no
+    /// value to remove.
+    pub fn take(&self) -> Option<Option<V>> {
+        let taken = self.outcome.take()?;
+        Some(taken.map(|(ref_v, mut_v)| L::join(ref_v, mut_v)))
+    }
+}
+
+impl<L, V> Clone for Removed<L, V>
+where
+    L: Layout<V>,
+{
+    fn clone(&self) -> Self {
+        Removed {
+            outcome: self.outcome.clone(),
+        }
+    }
+}
+
+/// The write and read handles to a newly created facade map
+pub(crate) type Handles<L, Key, V, Meta, Op> = (
+    WriteHandle<L, Key, V, Meta, Op>,
+    ReadHandle<L, Key, V, Meta>,
+);
+
+/// Create a facade map from `options`, and construct the read and write handles used to access
+/// it.
+pub(crate) fn with_options<L, Key, V, Meta, Op>(
+    options: MapOptions<Key, L::MutV, L::RefV, Meta>,
+) -> Handles<L, Key, V, Meta, Op>
+where
+    L: Layout<V>,
+    L::MutV: Mutable<Op>,
+    Key: StableHashEq + Clone,
+    Meta: Clone + 'static,
+{
+    let (w, r) = options.construct();
+    let read = ReadHandle {
+        handle: (*w).clone(),
+        layout: PhantomData,
+    };
+    let r = ReadHandle {
+        handle: r,
+        layout: PhantomData,
+    };
+    (WriteHandle { handle: w, read }, r)
+}
+
+impl<L, Key, V, Meta, Op> WriteHandle<L, Key, V, Meta, Op>
+where
+    L: Layout<V>,
+    L::MutV: Mutable<Op>,
+    Key: Eq + Hash + Clone,
+    Meta: Clone,
+{
+    pub fn publish(&mut self) {
+        self.handle.publish();
+    }
+
+    /// See [`handles::WriteHandle::take_evicted`].
+    pub fn take_evicted(&mut self) -> Vec<Key> {
+        self.handle.take_evicted()
+    }
+
+    pub fn has_pending(&self) -> bool {
+        self.handle.has_pending()
+    }
+
+    pub fn set_meta(&mut self, meta: Meta) {
+        self.handle.set_meta(meta);
+    }
+
+    pub fn insert(&mut self, k: Key, v: V) -> &mut Self {
+        let (ref_v, mut_v) = L::split(v);
+        self.handle.insert(k, ref_v, mut_v);
+        self
+    }
+
+    /// See [`handles::WriteHandle::insert_with_ttl`].
+    pub fn insert_with_ttl(&mut self, k: Key, v: V, ttl: Duration) -> &mut Self {
+        let (ref_v, mut_v) = L::split(v);
+        self.handle.insert_with_ttl(k, ref_v, mut_v, ttl);
+        self
+    }
+
+    /// See [`handles::WriteHandle::expire`].
+    pub fn expire(&mut self, now: Instant) -> &mut Self {
+        self.handle.expire(now);
+        self
+    }
+
+    pub fn remove(&mut self, k: Key) -> &mut Self {
+        self.handle.remove(k);
+        self
+    }
+
+    /// Remove the value at `k`, handing it back rather than dropping it, see
+    /// [`handles::WriteHandle::remove_and_take`].
+    pub fn remove_and_take(&mut self, k: Key) -> Removed<L, V> {
+        Removed {
+            outcome: self.handle.remove_and_take(k),
+        }
+    }
+
+    /// See [`handles::WriteHandle::rename`].
+    pub fn rename(&mut self, old: Key, new: Key) -> &mut Self {
+        self.handle.rename(old, new);
+        self
+    }
+
+    /// See [`handles::WriteHandle::swap`].
+    pub fn swap(&mut self, k1: Key, k2: Key) -> &mut Self {
+        self.handle.swap(k1, k2);
+        self
+    }
+
+    pub fn clear(&mut self) -> &mut Self {
+        self.handle.clear();
+        self
+    }
+}
+
+impl<Key, V, Meta, Op> WriteHandle<MutableOnly, Key, V, Meta, Op>
+where
+    V: Mutable<Op>,
+    Key: Eq + Hash + Clone,
+    Meta: Clone,
+{
+    pub fn mutate(&mut self, k: Key, op: Op) -> &mut Self {
+        self.handle.mutate(k, op);
+        self
+    }
+
+    /// See [`handles::WriteHandle::compare_and_mutate`].
+    pub fn compare_and_mutate(&mut self, k: Key, expected: V, op: Op) -> Outcome<bool>
+    where
+        V: PartialEq,
+    {
+        self.handle.compare_and_mutate(k, expected, op)
+    }
+
+    /// See [`handles::WriteHandle::compare_and_insert`].
+    pub fn compare_and_insert(&mut self, k: Key, expected: Option<V>, v: V) -> Outcome<bool>
+    where
+        V: PartialEq,
+    {
+        self.handle.compare_and_insert(k, expected, (), v)
+    }
+}
+
+impl<L, Key, V, Meta, Op> Extend<(Key, V)> for WriteHandle<L, Key, V, Meta, Op>
+where
+    L: Layout<V>,
+    L::MutV: Mutable<Op>,
+    Key: Eq + Hash + Clone,
+    Meta: Clone,
+{
+    fn extend<T: IntoIterator<Item = (Key, V)>>(&mut self, iter: T) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+// Allow using the write handle as a read handle
+impl<L, Key, V, Meta, Op> Deref for WriteHandle<L, Key, V, Meta, Op>
+where
+    L: Layout<V>,
+    L::MutV: Mutable<Op>,
+    Key: Eq + Hash + Clone,
+    Meta: Clone,
+{
+    type Target = ReadHandle<L, Key, V, Meta>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.read
+    }
+}
+
+impl<L, Key, V, Meta> Clone for ReadHandle<L, Key, V, Meta>
+where
+    L: Layout<V>,
+    Key: Eq + Hash + Clone,
+    Meta: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            handle: self.handle.clone(),
+            layout: PhantomData,
+        }
+    }
+}
+
+impl<L, Key, V, Meta> ReadHandle<L, Key, V, Meta>
+where
+    L: Layout<V>,
+    Key: Eq + Hash + Clone,
+    Meta: Clone,
+{
+    pub fn enter(&self) -> Option<MapReadRef<'_, L, Key, V, Meta>> {
+        Some(MapReadRef {
+            map: self.handle.enter()?,
+            layout: PhantomData,
+        })
+    }
+
+    pub fn meta(&self) -> Option<ReadGuard<'_, Meta>> {
+        self.handle.meta()
+    }
+
+    pub fn len(&self) -> usize {
+        self.handle.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handle.is_empty()
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<ReadGuard<'_, V>>
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let value = self.handle.get(key)?;
+        Some(ReadGuard::map(value, L::part))
+    }
+
+    /// See [`handles::ReadHandle::get_and`].
+    pub fn get_and<Q, F, R>(&self, key: &Q, then: F) -> Option<R>
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+        F: FnOnce(&V) -> R,
+    {
+        self.handle.get_and(key, |value| then(L::part(value)))
+    }
+
+    pub fn get_cloned<Q>(&self, key: &Q) -> Option<V>
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+        V: Clone,
+    {
+        self.get_and(key, V::clone)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.handle.contains_key(key)
+    }
+}
+
+impl<'rh, L, Key, V, Meta> MapReadRef<'rh, L, Key, V, Meta>
+where
+    L: Layout<V>,
+    Key: Eq + Hash,
+    Meta: Clone,
+{
+    /// Iterate over all (keys, values) in the map.
+    ///
+    /// Be careful with this function! While the iteration is ongoing, any writer that tries to
+    /// publish changes will block waiting on this reader to finish.
+    pub fn iter(&self) -> impl Iterator<Item = (&Key, &V)> {
+        self.map.iter().map(|(k, v)| (k, L::part(v)))
+    }
+
+    /// Iterate over all keys in the map, see [`MapReadRef::iter`].
+    pub fn keys(&self) -> impl Iterator<Item = &Key> {
+        self.map.keys()
+    }
+
+    /// Iterate over all values in the map, see [`MapReadRef::iter`].
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.map.values().map(L::part)
+    }
+
+    /// See [`read_ref::MapReadRef::len`].
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn meta(&self) -> &Meta {
+        self.map.meta()
+    }
+
+    pub fn get<Q>(&'rh self, key: &'_ Q) -> Option<&'rh V>
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.map.get(key).map(L::part)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.map.contains_key(key)
+    }
+}
@@ -0,0 +1,40 @@
+//! A map whose values are immutable once inserted, and so are allocated only once.
+//!
+//! This is a thin facade over a map with no mutable part, that is with a `MutV` of `()`, which
+//! hides the split between the two parts of each value. Its handles are those of the
+//! [`facade`] module, with values kept in the immutable part.
+
+use crate::{
+    MapOptions, Options,
+    facade::{self, Immutable},
+    stable_hash_eq::StableHashEq,
+};
+
+/// A write handle to an immutable map, see the [module documentation](self).
+pub type WriteHandle<Key, V, Meta = ()> = facade::WriteHandle<Immutable, Key, V, Meta, ()>;
+
+/// A read handle to an immutable map, see the [module documentation](self).
+pub type ReadHandle<Key, V, Meta = ()> = facade::ReadHandle<Immutable, Key, V, Meta>;
+
+/// A guard on one published version of an immutable map, see [`ReadHandle::enter`].
+pub type MapReadRef<'rh, Key, V, Meta = ()> = facade::MapReadRef<'rh, Immutable, Key, V, Meta>;
+
+/// Create an empty immutable map, and construct the read and write handles used to access it.
+pub fn new<Key, V>() -> (WriteHandle<Key, V>, ReadHandle<Key, V>)
+where
+    Key: StableHashEq + Clone,
+{
+    with_options(Options::default())
+}
+
+/// Create an immutable map from `options`, either plain [`Options`] or [`MapOptions`], and
+/// construct the read and write handles used to access it.
+pub fn with_options<Key, V, Meta>(
+    options: impl Into<MapOptions<Key, (), V, Meta>>,
+) -> (WriteHandle<Key, V, Meta>, ReadHandle<Key, V, Meta>)
+where
+    Key: StableHashEq + Clone,
+    Meta: Clone + 'static,
+{
+    facade::with_options(options.into())
+}
@@ -0,0 +1,163 @@
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+
+/// The secondary indexes registered on a map, see
+/// [`MapOptions::with_index`](crate::MapOptions::with_index). One per copy, see
+/// [`Inner`](crate::inner::Inner).
+pub(crate) struct Indexes<Key, RefV> {
+    indexes: Vec<(&'static str, BoxedIndex<Key, RefV>)>,
+}
+
+/// A registered index, boxed so that indexes with different key types can be kept together
+type BoxedIndex<Key, RefV> = Box<dyn AnyIndex<Key, RefV>>;
+
+/// Computes the index key that a value is filed under
+type Extract<Key, RefV, IndexKey> = Arc<dyn Fn(&Key, &RefV) -> IndexKey + Send + Sync>;
+
+/// A secondary index, with the type of its index key erased
+trait AnyIndex<Key, RefV>: Send + Sync {
+    fn insert(&mut self, key: &Key, ref_v: &RefV);
+    fn remove(&mut self, key: &Key, ref_v: &RefV);
+    fn clear(&mut self);
+    /// The keys filed under `index_key`, or `None` if it is not of this index's key type
+    fn keys(&self, index_key: &dyn Any) -> Option<Option<&HashSet<Key>>>;
+    /// An empty index with the same extractor
+    fn empty(&self) -> Box<dyn AnyIndex<Key, RefV>>;
+}
+
+struct Index<Key, RefV, IndexKey> {
+    extract: Extract<Key, RefV, IndexKey>,
+    entries: HashMap<IndexKey, HashSet<Key>>,
+}
+
+impl<Key, RefV, IndexKey> AnyIndex<Key, RefV> for Index<Key, RefV, IndexKey>
+where
+    Key: Eq + Hash + Clone + Send + Sync + 'static,
+    RefV: 'static,
+    IndexKey: Eq + Hash + Send + Sync + 'static,
+{
+    fn insert(&mut self, key: &Key, ref_v: &RefV) {
+        let index_key = (self.extract)(key, ref_v);
+        self.entries
+            .entry(index_key)
+            .or_default()
+            .insert(key.clone());
+    }
+
+    fn remove(&mut self, key: &Key, ref_v: &RefV) {
+        let index_key = (self.extract)(key, ref_v);
+        if let Some(keys) = self.entries.get_mut(&index_key) {
+            keys.remove(key);
+            if keys.is_empty() {
+                self.entries.remove(&index_key);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn keys(&self, index_key: &dyn Any) -> Option<Option<&HashSet<Key>>> {
+        let index_key = index_key.downcast_ref::<IndexKey>()?;
+        Some(self.entries.get(index_key))
+    }
+
+    fn empty(&self) -> Box<dyn AnyIndex<Key, RefV>> {
+        Box::new(Index {
+            extract: Arc::clone(&self.extract),
+            entries: HashMap::new(),
+        })
+    }
+}
+
+impl<Key, RefV> Indexes<Key, RefV> {
+    pub(crate) fn new() -> Self {
+        Indexes {
+            indexes: Vec::new(),
+        }
+    }
+
+    pub(crate) fn register<IndexKey, F>(&mut self, name: &'static str, extract: F)
+    where
+        Key: Eq + Hash + Clone + Send + Sync + 'static,
+        RefV: 'static,
+        IndexKey: Eq + Hash + Send + Sync + 'static,
+        F: Fn(&Key, &RefV) -> IndexKey + Send + Sync + 'static,
+    {
+        assert!(
+            self.indexes.iter().all(|(n, _)| *n != name),
+            "an index named {name:?} is already registered"
+        );
+        let index = Index {
+            extract: Arc::new(extract),
+            entries: HashMap::new(),
+        };
+        self.indexes.push((name, Box::new(index)));
+    }
+
+    pub(crate) fn insert(&mut self, key: &Key, ref_v: &RefV) {
+        for (_, index) in &mut self.indexes {
+            index.insert(key, ref_v);
+        }
+    }
+
+    pub(crate) fn remove(&mut self, key: &Key, ref_v: &RefV) {
+        for (_, index) in &mut self.indexes {
+            index.remove(key, ref_v);
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        for (_, index) in &mut self.indexes {
+            index.clear();
+        }
+    }
+
+    /// The keys filed under `index_key` in the index called `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no index called `name`, or if its index keys are not `IndexKey`s.
+    pub(crate) fn keys<IndexKey>(&self, name: &str, index_key: &IndexKey) -> Option<&HashSet<Key>>
+    where
+        IndexKey: 'static,
+    {
+        let (_, index) = self
+            .indexes
+            .iter()
+            .find(|(n, _)| *n == name)
+            .unwrap_or_else(|| panic!("no index named {name:?} is registered"));
+
+        index.keys(index_key).unwrap_or_else(|| {
+            panic!(
+                "index {name:?} is not keyed by {}",
+                std::any::type_name::<IndexKey>()
+            )
+        })
+    }
+}
+
+impl<Key, RefV> Clone for Indexes<Key, RefV> {
+    /// Clone the registered indexes, but none of their entries.
+    fn clone(&self) -> Self {
+        Indexes {
+            indexes: self
+                .indexes
+                .iter()
+                .map(|(name, index)| (*name, index.empty()))
+                .collect(),
+        }
+    }
+}
+
+impl<Key, RefV> fmt::Debug for Indexes<Key, RefV> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.indexes.iter().map(|(name, _)| name))
+            .finish()
+    }
+}
@@ -1,13 +1,34 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::hash::Hash;
+use std::mem::{ManuallyDrop, needs_drop, size_of};
+use std::ptr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
 
 use left_right::{
     Absorb,
     aliasing::{Aliased, DropBehavior},
 };
 
+use crate::aggregate::Aggregates;
+use crate::eviction::{Eviction, EvictionPolicy};
+use crate::index::Indexes;
 use crate::mutable::Mutable;
+use crate::order::KeyOrder;
+use crate::outcome::Outcome;
 
+/// Whether values need no aliasing between the two copies of the map, because their immutable
+/// part takes no space and dropping it does nothing, as for the `()` of a
+/// [`mutable_only`](crate::mutable_only) map.
+const fn unaliased<RefV>() -> bool {
+    size_of::<RefV>() == 0 && !needs_drop::<RefV>()
+}
+
+/// One of the two copies of the map.
+///
+/// Each copy holds its own eviction order, key order, secondary indexes, aggregates and
+/// deadlines, which the methods that change the set of keys in `data` keep in step with it.
 pub(crate) struct Inner<Key, MutV, RefV, Meta, D = crate::aliasing::NoDrop>
 where
     D: DropBehavior,
@@ -17,6 +38,16 @@ where
     pub(crate) data: HashMap<Key, Value<MutV, RefV, D>>,
     pub(crate) meta: Meta,
     pub(crate) ready: bool,
+    /// Counts writes, to stamp values with how recently they were written
+    pub(crate) tick: u64,
+    pub(crate) eviction: Option<Eviction<Key, MutV>>,
+    /// How many values expire at each deadline, to count expired values without a full scan
+    pub(crate) deadlines: BTreeMap<Instant, usize>,
+    /// The current read epoch, if readers record when they last looked values up
+    pub(crate) epoch: Option<Arc<AtomicU64>>,
+    pub(crate) indexes: Indexes<Key, RefV>,
+    pub(crate) order: KeyOrder<Key>,
+    pub(crate) aggregates: Aggregates<Key, MutV, RefV>,
 }
 
 pub struct Value<MutV, RefV, D>
@@ -28,12 +59,87 @@ where
     pub(crate) mut_v: MutV,
     /// Cannot be mutated whilst in the map; but is allocated only once
     pub(crate) ref_v: Aliased<RefV, D>,
+    /// Bookkeeping for time-to-live, eviction and access tracking, boxed so that maps which use
+    /// none of them pay for only a pointer
+    pub(crate) extra: Option<Box<Extra>>,
+}
+
+/// The bookkeeping a value carries only if the map uses a feature which needs it
+#[derive(Clone, Default)]
+pub(crate) struct Extra {
+    /// The instant after which readers treat this value as absent
+    deadline: Option<Instant>,
+    /// The tick at which this value was inserted, or last written if evicting by write recency
+    stamp: u64,
+    /// The read epoch at which a reader last looked this value up. This is shared between both
+    /// copies, so that readers can update it whichever copy they are reading.
+    last_read: Option<Arc<AtomicU64>>,
+}
+
+impl<MutV, RefV, D> Value<MutV, RefV, D>
+where
+    D: DropBehavior,
+    MutV: Clone,
+{
+    fn expires_at(&self) -> Option<Instant> {
+        self.extra.as_ref()?.deadline
+    }
+
+    /// Whether this value's time-to-live had run out by `now`
+    pub(crate) fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at().is_some_and(|deadline| deadline <= now)
+    }
+
+    /// Whether this value should be visible to readers right now
+    pub(crate) fn is_live(&self) -> bool {
+        // Only consult the clock for values that can actually expire
+        self.expires_at().is_none() || !self.is_expired(Instant::now())
+    }
+
+    /// The tick at which this value was inserted, or last written if evicting by write recency.
+    /// Only kept by maps with a maximum number of entries.
+    pub(crate) fn stamp(&self) -> u64 {
+        self.extra.as_ref().map_or(0, |extra| extra.stamp)
+    }
+
+    fn last_read_cell(&self) -> Option<&AtomicU64> {
+        self.extra.as_ref()?.last_read.as_deref()
+    }
+
+    /// Record that a reader looked this value up during `epoch`
+    pub(crate) fn record_read(&self, epoch: Option<&AtomicU64>) {
+        if let (Some(last_read), Some(epoch)) = (self.last_read_cell(), epoch) {
+            last_read.store(epoch.load(Ordering::Relaxed), Ordering::Relaxed);
+        }
+    }
+
+    fn last_read_epoch(&self) -> u64 {
+        self.last_read_cell()
+            .map_or(0, |last_read| last_read.load(Ordering::Relaxed))
+    }
+
+    fn extra_mut(&mut self) -> &mut Extra {
+        self.extra.get_or_insert_with(Box::default)
+    }
 }
 
 impl<MutV, RefV> Value<MutV, RefV, crate::aliasing::NoDrop>
 where
     MutV: Clone,
 {
+    pub(crate) fn new(ref_v: RefV, mut_v: MutV, deadline: Option<Instant>) -> Self {
+        Value {
+            mut_v,
+            ref_v: Aliased::from(ref_v),
+            extra: deadline.map(|deadline| {
+                Box::new(Extra {
+                    deadline: Some(deadline),
+                    ..Extra::default()
+                })
+            }),
+        }
+    }
+
     /// Read the mutable state of this value
     ///
     /// Note that this doesn't return a mut ref;
@@ -48,6 +154,18 @@ where
         self.ref_v.as_ref()
     }
 
+    /// The instant this value expires at, if it was inserted with a time-to-live
+    pub fn deadline(&self) -> Option<Instant> {
+        self.expires_at()
+    }
+
+    /// The read epoch at which a reader last looked this value up, if access tracking is
+    /// enabled. See [`Options::with_access_tracking`](crate::Options::with_access_tracking).
+    pub fn last_read(&self) -> Option<u64> {
+        self.last_read_cell()
+            .map(|last_read| last_read.load(Ordering::Relaxed))
+    }
+
     /// Produce a copy of this Value by:
     /// - Aliasing the immutable part (RefV)
     /// - Cloning the mutable part (MutV)
@@ -63,6 +181,7 @@ where
             // - Our implemenation of Absorb ensures proper
             //   drop order.
             ref_v: unsafe { self.ref_v.alias() },
+            extra: self.extra.clone(),
         }
     }
 
@@ -77,34 +196,123 @@ where
         Value {
             mut_v: self.mut_v,
             ref_v: unsafe { self.ref_v.change_drop() },
+            extra: self.extra,
         }
     }
 }
 
-pub(crate) enum Operation<Key, MutV, RefV, Meta, Op>
+impl<MutV, RefV> Value<MutV, RefV, crate::aliasing::DoDrop>
+where
+    MutV: Clone,
+{
+    /// Take ownership of both parts of this value.
+    ///
+    /// A `DoDrop` value is the last alias of its immutable part, so that part can be moved out
+    /// rather than dropped.
+    pub(crate) fn into_parts(self) -> (RefV, MutV) {
+        let ref_v = ManuallyDrop::new(self.ref_v);
+        // SAFETY: no other alias of `ref_v` remains, and the `ManuallyDrop` ensures it is not also
+        // dropped by the `Aliased` it was read out of.
+        let ref_v = unsafe { ptr::read(ref_v.as_ref()) };
+        (ref_v, self.mut_v)
+    }
+}
+
+pub(crate) enum Operation<Key, MutV, RefV, Meta, Op, MetaOp>
 where
     MutV: Clone,
 {
     Insert(Key, Value<MutV, RefV, crate::aliasing::NoDrop>),
     Remove(Key),
+    /// Remove a key, handing its value back once the last alias of it has been removed.
+    RemoveAndTake(Key, Outcome<Option<(RefV, MutV)>>),
+    /// Move the value at the first key to the second, replacing any value already there.
+    Rename(Key, Key),
+    /// Exchange the values at two keys, moving a value over if only one of them has one.
+    Swap(Key, Key),
     Clear,
+    /// Remove every value whose time-to-live had run out by the given instant.
+    Expire(Instant),
 
     SetMeta(Meta),
+    MutateMeta(MetaOp),
 
     /// Mark the map as ready to be consumed for readers.
     MarkReady,
 
     Mutate(Key, Op),
+
+    /// Apply `Op` only if the key is present and its `MutV` is as expected.
+    CompareAndMutate(Key, Expected<MutV>, Op, Outcome<bool>),
+    /// Insert only if the current `MutV` (or absence) of the key is as expected.
+    CompareAndInsert(
+        Key,
+        Expected<MutV>,
+        Value<MutV, RefV, crate::aliasing::NoDrop>,
+        Outcome<bool>,
+    ),
+}
+
+/// The state a key is expected to be in for a compare-and-set operation to go ahead
+pub(crate) struct Expected<MutV> {
+    /// `None` if the key is expected to be absent
+    pub(crate) mut_v: Option<MutV>,
+    pub(crate) eq: fn(&MutV, &MutV) -> bool,
+    /// The decision made by `absorb_first`, for `absorb_second` to follow
+    pub(crate) matched: Option<bool>,
+}
+
+impl<MutV> Expected<MutV> {
+    pub(crate) fn matches(&self, current: Option<&MutV>) -> bool {
+        match (&self.mut_v, current) {
+            (Some(expected), Some(current)) => (self.eq)(expected, current),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    /// Decide whether the operation goes ahead, given the live value at its key.
+    ///
+    /// Whether a value is live depends on the clock, which may move on between the two absorbs,
+    /// so the first decision made is recorded and repeated for the second copy.
+    pub(crate) fn decide(&mut self, current: Option<&MutV>) -> bool {
+        if let Some(matched) = self.matched {
+            return matched;
+        }
+        let matched = self.matches(current);
+        self.matched = Some(matched);
+        matched
+    }
+}
+
+impl<Key, MutV, RefV, Meta, Op, MetaOp> Operation<Key, MutV, RefV, Meta, Op, MetaOp>
+where
+    MutV: Clone,
+{
+    /// Drop an operation which was never handed to the oplog.
+    ///
+    /// Operations are created holding the only alias of any value they carry, so an operation
+    /// that never reached `absorb_first` must drop that value itself rather than leak it.
+    pub(crate) fn discard(self) {
+        if let Operation::Insert(_, value) | Operation::CompareAndInsert(_, _, value, _) = self {
+            // Safety: this operation was never absorbed, so `value` is the only alias
+            drop(unsafe { value.change_drop::<crate::aliasing::DoDrop>() });
+        }
+    }
 }
 
-impl<Key, MutV, RefV, Meta, Op> Absorb<Operation<Key, MutV, RefV, Meta, Op>>
+impl<Key, MutV, RefV, Meta, Op, MetaOp> Absorb<Operation<Key, MutV, RefV, Meta, Op, MetaOp>>
     for Inner<Key, MutV, RefV, Meta>
 where
     Key: Eq + Hash + Clone,
     MutV: Mutable<Op> + Clone,
-    Meta: Clone,
+    Meta: Mutable<MetaOp> + Clone,
 {
-    fn absorb_first(&mut self, op: &mut Operation<Key, MutV, RefV, Meta, Op>, _other: &Self) {
+    fn absorb_first(
+        &mut self,
+        op: &mut Operation<Key, MutV, RefV, Meta, Op, MetaOp>,
+        _other: &Self,
+    ) {
         // Safety note for calls to .alias():
         //
         //   it is safe to alias this value here because if it is ever removed, one alias is always
@@ -115,32 +323,79 @@ where
 
         match *op {
             Operation::Insert(ref key, ref mut value) => {
-                self.data
-                    .insert(key.clone(), unsafe { value.alias_clone() });
+                self.track_reads(value);
+                self.put(key.clone(), unsafe { value.alias_clone() });
+                self.evict(true);
+            }
+            Operation::Remove(ref key) | Operation::RemoveAndTake(ref key, _) => {
+                self.take(key);
             }
-            Operation::Remove(ref key) => {
-                self.data.remove(key);
+            Operation::Rename(ref old, ref new) => {
+                self.rename(old, new.clone());
+            }
+            Operation::Swap(ref k1, ref k2) => {
+                self.swap(k1, k2);
             }
             Operation::Clear => {
-                self.data.clear();
+                self.clear();
+            }
+            Operation::Expire(now) => {
+                self.expire(now);
             }
             Operation::SetMeta(ref meta) => {
                 self.meta = meta.clone();
             }
+            Operation::MutateMeta(ref mut operation) => {
+                Mutable::mutate_first(&mut self.meta, operation);
+            }
             Operation::MarkReady => {
                 self.ready = true;
             }
             Operation::Mutate(ref key, ref mut operation) => {
-                if let Some(value) = self.data.get_mut(key) {
-                    let mut_v = &mut value.mut_v;
+                self.mutate_with(key, |mut_v| Mutable::mutate_first(mut_v, operation));
+            }
+            Operation::CompareAndMutate(
+                ref key,
+                ref mut expected,
+                ref mut operation,
+                ref outcome,
+            ) => {
+                let matched = expected.decide(self.live_mut_v(key));
 
-                    Mutable::mutate_first(mut_v, operation);
+                outcome.set(matched);
+                if matched {
+                    self.mutate_with(key, |mut_v| Mutable::mutate_first(mut_v, operation));
+                }
+            }
+            Operation::CompareAndInsert(ref key, ref mut expected, ref mut value, ref outcome) => {
+                let matched = expected.decide(self.live_mut_v(key));
+
+                outcome.set(matched);
+                if matched {
+                    self.track_reads(value);
+                    self.put(key.clone(), unsafe { value.alias_clone() });
+                    self.evict(true);
                 }
             }
         }
     }
 
-    fn absorb_second(&mut self, op: Operation<Key, MutV, RefV, Meta, Op>, _other: &Self) {
+    fn absorb_second(&mut self, op: Operation<Key, MutV, RefV, Meta, Op, MetaOp>, _other: &Self) {
+        // Values which need no aliasing are inserted and removed in this copy as they are, since
+        // dropping them with `NoDrop` leaves nothing of their immutable part behind.
+        let op = match op {
+            Operation::Insert(key, value) if unaliased::<RefV>() => {
+                self.put(key, value);
+                self.evict(false);
+                return;
+            }
+            Operation::Remove(key) if unaliased::<RefV>() => {
+                self.take(&key);
+                return;
+            }
+            op => op,
+        };
+
         // # Safety (for cast):
         //
         // See the module-level documentation for left_right::aliasing.
@@ -162,27 +417,55 @@ where
         //   and at the end of scope we revert to `NoDrop`, so all is well.
         match op {
             Operation::Insert(key, value) => {
-                inner
-                    .data
-                    .insert(key.clone(), unsafe { value.change_drop() });
+                inner.put(key, unsafe { value.change_drop() });
+                inner.evict(false);
             }
             Operation::Remove(key) => {
-                inner.data.remove(&key);
+                inner.take(&key);
+            }
+            Operation::RemoveAndTake(key, outcome) => {
+                // absorb_first removed the other alias without dropping it, so this is the last
+                outcome.set(inner.take(&key).map(Value::into_parts));
+            }
+            Operation::Rename(old, new) => {
+                // Any value displaced from `new` was dropped by absorb_first with NoDrop, so this
+                // is the last alias and it is dropped here.
+                inner.rename(&old, new);
+            }
+            Operation::Swap(k1, k2) => {
+                inner.swap(&k1, &k2);
             }
             Operation::Clear => {
-                inner.data.clear();
+                inner.clear();
+            }
+            Operation::Expire(now) => {
+                inner.expire(now);
             }
             Operation::SetMeta(meta) => {
                 inner.meta = meta;
             }
+            Operation::MutateMeta(operation) => {
+                Mutable::mutate_second(&mut inner.meta, operation);
+            }
             Operation::MarkReady => {
                 inner.ready = true;
             }
             Operation::Mutate(key, operation) => {
-                if let Some(value) = self.data.get_mut(&key) {
-                    let mut_v = &mut value.mut_v;
-
-                    Mutable::mutate_second(mut_v, operation);
+                inner.mutate_with(&key, |mut_v| Mutable::mutate_second(mut_v, operation));
+            }
+            Operation::CompareAndMutate(key, mut expected, operation, _) => {
+                // Follows the decision made by absorb_first
+                if expected.decide(inner.live_mut_v(&key)) {
+                    inner.mutate_with(&key, |mut_v| Mutable::mutate_second(mut_v, operation));
+                }
+            }
+            Operation::CompareAndInsert(key, mut expected, value, _) => {
+                // If absorb_first did not insert its alias then `value` is the only alias, and
+                // is dropped here.
+                let value = unsafe { value.change_drop() };
+                if expected.decide(inner.live_mut_v(&key)) {
+                    inner.put(key, value);
+                    inner.evict(false);
                 }
             }
         }
@@ -232,12 +515,23 @@ where
             // so we are about to turn the alias back into NoDrop.
             (k.clone(), unsafe { vs.alias_clone().change_drop() })
         }));
+        for (key, value) in &inner.data {
+            inner.indexes.insert(key, value.ref_v.as_ref());
+            inner.order.insert(key);
+            inner
+                .aggregates
+                .insert(key, value.ref_v.as_ref(), &value.mut_v);
+        }
+        inner.deadlines.clone_from(&first.deadlines);
+        inner.tick = first.tick;
+        inner.eviction.clone_from(&first.eviction);
         self.ready = true;
     }
 }
 
 impl<Key, MutV, RefV, Meta> Clone for Inner<Key, MutV, RefV, Meta>
 where
+    Key: Clone,
     MutV: Clone,
     Meta: Clone,
 {
@@ -250,6 +544,13 @@ where
             ),
             meta: self.meta.clone(),
             ready: self.ready,
+            tick: self.tick,
+            eviction: self.eviction.clone(),
+            deadlines: BTreeMap::new(),
+            epoch: self.epoch.clone(),
+            indexes: self.indexes.clone(),
+            order: self.order.clone(),
+            aggregates: self.aggregates.clone(),
         }
     }
 }
@@ -265,7 +566,27 @@ where
             data: HashMap::with_capacity(capacity),
             meta,
             ready: false,
+            tick: 0,
+            eviction: None,
+            deadlines: BTreeMap::new(),
+            epoch: None,
+            indexes: Indexes::new(),
+            order: KeyOrder::new(),
+            aggregates: Aggregates::new(),
+        }
+    }
+
+    /// The number of values readers can see, leaving out those whose time-to-live has run out.
+    pub(crate) fn live_len(&self) -> usize {
+        if self.deadlines.is_empty() {
+            return self.data.len();
         }
+        let expired: usize = self
+            .deadlines
+            .range(..=Instant::now())
+            .map(|(_, n)| n)
+            .sum();
+        self.data.len() - expired
     }
 
     pub(crate) fn new(meta: Meta) -> Self {
@@ -273,6 +594,226 @@ where
             data: HashMap::new(),
             meta,
             ready: false,
+            tick: 0,
+            eviction: None,
+            deadlines: BTreeMap::new(),
+            epoch: None,
+            indexes: Indexes::new(),
+            order: KeyOrder::new(),
+            aggregates: Aggregates::new(),
+        }
+    }
+}
+
+impl<Key, MutV, RefV, Meta, D> Inner<Key, MutV, RefV, Meta, D>
+where
+    Key: Eq + Hash + Clone,
+    D: DropBehavior,
+    MutV: Clone,
+    Meta: Clone,
+{
+    // Every change to the set of keys in the map goes through the methods below, see `Inner`.
+
+    /// Insert `value` at `key`, stamping it as the most recent write.
+    fn put(&mut self, key: Key, mut value: Value<MutV, RefV, D>) {
+        self.tick += 1;
+        if let Some(eviction) = self.eviction.as_mut() {
+            value.extra_mut().stamp = self.tick;
+            if eviction.is_ordered() {
+                eviction.order.insert(self.tick, key.clone());
+            }
+        }
+        if let Some(replaced) = self.data.get(&key) {
+            self.indexes.remove(&key, replaced.ref_v.as_ref());
+            self.aggregates
+                .remove(&key, replaced.ref_v.as_ref(), &replaced.mut_v);
+        }
+        self.indexes.insert(&key, value.ref_v.as_ref());
+        self.order.insert(&key);
+        self.aggregates
+            .insert(&key, value.ref_v.as_ref(), &value.mut_v);
+        if let Some(deadline) = value.expires_at() {
+            *self.deadlines.entry(deadline).or_default() += 1;
+        }
+        if let Some(replaced) = self.data.insert(key, value) {
+            self.forget(&replaced);
+        }
+    }
+
+    /// Remove the value at `key`.
+    fn take(&mut self, key: &Key) -> Option<Value<MutV, RefV, D>> {
+        let value = self.data.remove(key)?;
+        self.indexes.remove(key, value.ref_v.as_ref());
+        self.order.remove(key);
+        self.aggregates
+            .remove(key, value.ref_v.as_ref(), &value.mut_v);
+        self.forget(&value);
+        Some(value)
+    }
+
+    /// Move the value at `old` to `new`, stamping it as the most recent write.
+    ///
+    /// The value itself is moved, so both copies keep the same alias of its immutable part.
+    fn rename(&mut self, old: &Key, new: Key) {
+        if let Some(value) = self.take(old) {
+            self.put(new, value);
+        }
+    }
+
+    /// Exchange the values at `k1` and `k2`, stamping each as the most recent write.
+    fn swap(&mut self, k1: &Key, k2: &Key) {
+        if k1 == k2 {
+            return;
+        }
+        let v1 = self.take(k1);
+        let v2 = self.take(k2);
+        if let Some(value) = v1 {
+            self.put(k2.clone(), value);
+        }
+        if let Some(value) = v2 {
+            self.put(k1.clone(), value);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.data.clear();
+        self.indexes.clear();
+        self.order.clear();
+        self.aggregates.clear();
+        self.deadlines.clear();
+        if let Some(eviction) = self.eviction.as_mut() {
+            eviction.order.clear();
+        }
+    }
+
+    fn expire(&mut self, now: Instant) {
+        let mut order = self.eviction.as_mut().map(|eviction| &mut eviction.order);
+        let indexes = &mut self.indexes;
+        let key_order = &mut self.order;
+        let aggregates = &mut self.aggregates;
+        self.data.retain(|key, value| {
+            if !value.is_expired(now) {
+                return true;
+            }
+            if let Some(order) = order.as_mut() {
+                order.remove(&value.stamp());
+            }
+            indexes.remove(key, value.ref_v.as_ref());
+            key_order.remove(key);
+            aggregates.remove(key, value.ref_v.as_ref(), &value.mut_v);
+            false
+        });
+        self.deadlines.retain(|&deadline, _| deadline > now);
+    }
+
+    /// Drop a value which has left the map from the eviction order and the deadlines.
+    fn forget(&mut self, value: &Value<MutV, RefV, D>) {
+        if let Some(eviction) = self.eviction.as_mut() {
+            eviction.order.remove(&value.stamp());
+        }
+        if let Some(deadline) = value.expires_at()
+            && let Some(count) = self.deadlines.get_mut(&deadline)
+        {
+            *count -= 1;
+            if *count == 0 {
+                self.deadlines.remove(&deadline);
+            }
+        }
+    }
+
+    /// The mutable part of the value at `key`, if readers can see it.
+    fn live_mut_v(&self, key: &Key) -> Option<&MutV> {
+        self.data
+            .get(key)
+            .filter(|value| value.is_live())
+            .map(|value| &value.mut_v)
+    }
+
+    /// Mutate the value at `key` in place with `f`, if there is one.
+    fn mutate_with(&mut self, key: &Key, f: impl FnOnce(&mut MutV)) {
+        let Some(value) = self.data.get_mut(key) else {
+            return;
+        };
+
+        self.aggregates
+            .remove(key, value.ref_v.as_ref(), &value.mut_v);
+        f(&mut value.mut_v);
+        self.aggregates
+            .insert(key, value.ref_v.as_ref(), &value.mut_v);
+        self.written(key);
+    }
+
+    /// Note that the value at `key` has been mutated.
+    fn written(&mut self, key: &Key) {
+        let Some(eviction) = self.eviction.as_mut().filter(|e| e.stamps_writes()) else {
+            return;
+        };
+        let Some(value) = self.data.get_mut(key) else {
+            return;
+        };
+
+        self.tick += 1;
+        eviction.order.remove(&value.stamp());
+        value.extra_mut().stamp = self.tick;
+        eviction.order.insert(self.tick, key.clone());
+    }
+
+    /// Give `value` somewhere for readers to record when they last looked it up, if they do.
+    ///
+    /// This is done to the operation itself in `absorb_first`, so that both copies share it.
+    fn track_reads(&self, value: &mut Value<MutV, RefV, D>) {
+        if let Some(epoch) = self.epoch.as_ref() {
+            let epoch = epoch.load(Ordering::Relaxed);
+            value.extra_mut().last_read = Some(Arc::new(AtomicU64::new(epoch)));
+        }
+    }
+
+    /// Choose the next value to evict, if the map holds more than its maximum number of entries.
+    ///
+    /// Both copies hold the same values with the same stamps, so both choose the same victim.
+    /// The exception is `LeastRecentlyRead`, which depends on readers: `absorb_first` queues its
+    /// choices for `absorb_second` to follow.
+    fn victim(&self, first: bool) -> Option<Key> {
+        let eviction = self.eviction.as_ref()?;
+        if self.data.len() <= eviction.max_entries {
+            return None;
+        }
+
+        match eviction.policy {
+            EvictionPolicy::LeastRecentlyRead if !first => {
+                eviction.decided.lock().unwrap().pop_front()
+            }
+            EvictionPolicy::LeastRecentlyRead => {
+                let victim = self
+                    .data
+                    .iter()
+                    .min_by_key(|(_, value)| (value.last_read_epoch(), value.stamp()))
+                    .map(|(key, _)| key.clone())?;
+
+                eviction.decided.lock().unwrap().push_back(victim.clone());
+                Some(victim)
+            }
+            EvictionPolicy::Lru | EvictionPolicy::Fifo => {
+                eviction.order.first_key_value().map(|(_, key)| key.clone())
+            }
+            EvictionPolicy::LowestMutV(cmp) => self
+                .data
+                .iter()
+                .min_by(|(_, a), (_, b)| cmp(&a.mut_v, &b.mut_v).then(a.stamp().cmp(&b.stamp())))
+                .map(|(key, _)| key.clone()),
+        }
+    }
+
+    /// Evict values until the map is back within its maximum number of entries.
+    ///
+    /// `first` is set in `absorb_first`, where the keys of evicted values are also handed to the
+    /// write handle.
+    fn evict(&mut self, first: bool) {
+        while let Some(victim) = self.victim(first) {
+            self.take(&victim);
+            if first && let Some(eviction) = self.eviction.as_ref() {
+                eviction.evicted.lock().unwrap().push(victim);
+            }
         }
     }
 }
@@ -2,27 +2,57 @@
 // But, currently, it does not..
 #![deny(unreachable_pub)]
 
+use crate::aggregate::Aggregates;
+use crate::eviction::Eviction;
+use crate::index::Indexes;
 use crate::inner::Inner;
 use crate::inner::Operation;
 use crate::mutable::Mutable;
+use crate::order::KeyOrder;
 use crate::read::ReadHandle;
 use crate::stable_hash_eq::StableHashEq;
 use crate::write::WriteHandle;
 
 use std::hash::Hash;
+use std::marker::PhantomData;
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Mutex};
 
+mod aggregate;
+mod combinators;
+pub mod crdt;
+mod eviction;
+pub mod facade;
+pub mod immutable;
+mod index;
 mod inner;
 mod mutable;
+pub mod mutable_only;
+mod ops;
+mod order;
+mod outcome;
 mod read;
 mod read_ref;
+pub mod sharded;
 mod stable_hash_eq;
+mod transaction;
 mod write;
 
 pub mod handles {
+    pub use crate::outcome::Outcome;
+    pub use crate::read::Cursor;
     pub use crate::read::ReadHandle;
+    pub use crate::transaction::Transaction;
+    pub use crate::write::Savepoint;
     pub use crate::write::WriteHandle;
 }
 
+/// The write and read handles to a newly created map
+pub(crate) type Handles<Key, MutV, RefV, Meta, Op, MetaOp = ()> = (
+    WriteHandle<Key, MutV, RefV, Meta, Op, MetaOp>,
+    ReadHandle<Key, MutV, RefV, Meta>,
+);
+
 pub mod refs {
     pub use crate::inner::Value;
     pub use crate::read_ref::MapReadRef;
@@ -36,16 +66,23 @@ pub mod refs {
 }
 
 pub mod muts {
-    pub use crate::mutable::Mutable;
+    pub use crate::combinators::{Either, Field, Seq};
+    pub use crate::mutable::{Moving, Mutable, OwnedOp};
+    pub use crate::ops::{Counter, FnOp, MapOp, OptionOp, SetOp, VecOp};
 }
 
+pub use crate::aggregate::Aggregate;
+pub use crate::eviction::EvictionPolicy;
+
 // NOTE: It is _critical_ that this module is not public.
 mod aliasing;
 
 #[derive(Debug)]
-pub struct Options<Meta> {
+pub struct Options<Meta, MetaOp = ()> {
     meta: Meta,
     capacity: Option<usize>,
+    access_tracking: bool,
+    meta_op: PhantomData<fn(MetaOp)>,
 }
 
 impl Default for Options<()> {
@@ -53,35 +90,185 @@ impl Default for Options<()> {
         Options {
             meta: (),
             capacity: None,
+            access_tracking: false,
+            meta_op: PhantomData,
         }
     }
 }
 
-impl<Meta> Options<Meta> {
-    pub fn with_meta<M2>(self, meta: M2) -> Options<M2> {
+impl<Meta, MetaOp> Options<Meta, MetaOp> {
+    pub fn with_meta<M2>(self, meta: M2) -> Options<M2, MetaOp> {
         Options {
             meta,
             capacity: self.capacity,
+            access_tracking: self.access_tracking,
+            meta_op: PhantomData,
         }
     }
 
-    pub fn with_capacity(self, capacity: usize) -> Options<Meta> {
+    /// Set the type of operation used to mutate the meta in place with
+    /// [`WriteHandle::mutate_meta`](crate::handles::WriteHandle::mutate_meta).
+    pub fn with_meta_op<MO>(self) -> Options<Meta, MO> {
         Options {
             meta: self.meta,
+            capacity: self.capacity,
+            access_tracking: self.access_tracking,
+            meta_op: PhantomData,
+        }
+    }
+
+    pub fn with_capacity(self, capacity: usize) -> Options<Meta, MetaOp> {
+        Options {
             capacity: Some(capacity),
+            ..self
+        }
+    }
+
+    /// Have readers record when they last looked each value up, in the form of a read epoch
+    /// which advances with every [`publish`](crate::handles::WriteHandle::publish).
+    ///
+    /// Each value then carries a shared atomic which [`ReadHandle::get`] and
+    /// [`MapReadRef::get`](crate::refs::MapReadRef::get) update. It lives outside of the two
+    /// copies of the map, so readers never write to the map itself. See
+    /// [`ReadHandle::hottest`] and [`EvictionPolicy::LeastRecentlyRead`].
+    pub fn with_access_tracking(self) -> Options<Meta, MetaOp> {
+        Options {
+            access_tracking: true,
+            ..self
         }
     }
 
-    pub fn construct<Key, MutV, RefV, Op>(
+    /// Fix the types of the map's keys and values, to configure the options which depend on
+    /// them, such as [`MapOptions::with_max_entries`] and [`MapOptions::with_index`].
+    pub fn for_map<Key, MutV, RefV>(self) -> MapOptions<Key, MutV, RefV, Meta, MetaOp> {
+        MapOptions {
+            options: self,
+            max_entries: None,
+            indexes: Indexes::new(),
+            order: KeyOrder::new(),
+            aggregates: Aggregates::new(),
+        }
+    }
+
+    pub fn construct<Key, MutV, RefV, Op>(self) -> Handles<Key, MutV, RefV, Meta, Op, MetaOp>
+    where
+        Key: StableHashEq + Clone,
+        MutV: Mutable<Op> + Clone,
+        Meta: Mutable<MetaOp> + Clone + 'static,
+    {
+        self.for_map().construct()
+    }
+
+    /// Create the map, and construct the read and write handles used to access it.
+    ///
+    /// # Safety
+    ///
+    /// See [`MapOptions::assert_stable`].
+    pub unsafe fn assert_stable<Key, MutV, RefV, Op>(
         self,
-    ) -> (
-        WriteHandle<Key, MutV, RefV, Meta, Op>,
-        ReadHandle<Key, MutV, RefV, Meta>,
-    )
+    ) -> Handles<Key, MutV, RefV, Meta, Op, MetaOp>
+    where
+        Key: Eq + Hash + Clone,
+        MutV: Mutable<Op> + Clone,
+        Meta: Mutable<MetaOp> + Clone + 'static,
+    {
+        unsafe { self.for_map().assert_stable() }
+    }
+}
+
+/// [`Options`] for a map whose key and value types are known, see [`Options::for_map`].
+#[derive(Debug)]
+pub struct MapOptions<Key, MutV, RefV, Meta = (), MetaOp = ()> {
+    options: Options<Meta, MetaOp>,
+    max_entries: Option<(usize, EvictionPolicy<MutV>)>,
+    indexes: Indexes<Key, RefV>,
+    order: KeyOrder<Key>,
+    aggregates: Aggregates<Key, MutV, RefV>,
+}
+
+impl<Key, MutV, RefV, Meta, MetaOp> From<Options<Meta, MetaOp>>
+    for MapOptions<Key, MutV, RefV, Meta, MetaOp>
+{
+    fn from(options: Options<Meta, MetaOp>) -> Self {
+        options.for_map()
+    }
+}
+
+impl<Key, MutV, RefV, Meta, MetaOp> MapOptions<Key, MutV, RefV, Meta, MetaOp> {
+    /// Bound the map to at most `max_entries` values. Whenever an insert takes the map over
+    /// this bound, values are evicted according to `policy` until it is back within it.
+    ///
+    /// Evictions are applied identically to both copies of the map, and the keys of evicted
+    /// values can be taken with
+    /// [`WriteHandle::take_evicted`](crate::handles::WriteHandle::take_evicted).
+    pub fn with_max_entries(self, max_entries: usize, policy: EvictionPolicy<MutV>) -> Self {
+        MapOptions {
+            max_entries: Some((max_entries, policy)),
+            ..self
+        }
+    }
+
+    /// Maintain a secondary index called `name` alongside the map, which files the key of every
+    /// value under `extract(key, ref_v)`. Look values up through it with
+    /// [`MapReadRef::by_index`](crate::refs::MapReadRef::by_index).
+    ///
+    /// Both copies of the map keep their own copy of the index, so `extract` is called twice for
+    /// every value inserted or removed and must be deterministic. Since it only sees the
+    /// immutable part of each value, mutating a value never moves it within the index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index called `name` has already been registered.
+    pub fn with_index<IndexKey, F>(mut self, name: &'static str, extract: F) -> Self
+    where
+        Key: Eq + Hash + Clone + Send + Sync + 'static,
+        RefV: 'static,
+        IndexKey: Eq + Hash + Send + Sync + 'static,
+        F: Fn(&Key, &RefV) -> IndexKey + Send + Sync + 'static,
+    {
+        self.indexes.register(name, extract);
+        self
+    }
+
+    /// Keep the keys of the map in ascending order alongside it, so that each page of a
+    /// [`ReadHandle::scan`](crate::handles::ReadHandle::scan) resumes straight from its cursor
+    /// rather than walking the whole map.
+    ///
+    /// Both copies of the map keep their own order, so every insert and remove also updates two
+    /// ordered sets of keys.
+    pub fn with_ordered_keys(mut self) -> Self
+    where
+        Key: Ord + Clone + Send + Sync + 'static,
+    {
+        self.order.enable();
+        self
+    }
+
+    /// Maintain an aggregate called `name` over the map, combining the projections of its values
+    /// according to `kind`. Values which `project` to `None` are left out of it. Read it with
+    /// [`MapReadRef::aggregate`](crate::refs::MapReadRef::aggregate).
+    ///
+    /// Aggregates are updated incrementally whenever a value is inserted, mutated or removed, so
+    /// `project` is called on both copies of the map and must be deterministic. Unlike
+    /// [`MapReadRef::len`](crate::refs::MapReadRef::len), they include values whose
+    /// time-to-live has run out until those values are removed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an aggregate called `name` has already been registered.
+    pub fn with_aggregate<F>(mut self, name: &'static str, kind: Aggregate, project: F) -> Self
+    where
+        F: Fn(&Key, &RefV, &MutV) -> Option<i64> + Send + Sync + 'static,
+    {
+        self.aggregates.register(name, kind, project);
+        self
+    }
+
+    pub fn construct<Op>(self) -> Handles<Key, MutV, RefV, Meta, Op, MetaOp>
     where
         Key: StableHashEq + Clone,
         MutV: Mutable<Op> + Clone,
-        Meta: Clone + 'static,
+        Meta: Mutable<MetaOp> + Clone + 'static,
     {
         // Safety: K: StableHashEq
         unsafe { self.assert_stable() }
@@ -95,36 +282,57 @@ impl<Meta> Options<Meta> {
     /// deterministic. That is, they must always yield the same result if given the same inputs.
     /// For keys of type `K`, the result must also be consistent between different clones of the
     /// same key.
-    pub unsafe fn assert_stable<Key, MutV, RefV, Op>(
-        self,
-    ) -> (
-        WriteHandle<Key, MutV, RefV, Meta, Op>,
-        ReadHandle<Key, MutV, RefV, Meta>,
-    )
+    pub unsafe fn assert_stable<Op>(self) -> Handles<Key, MutV, RefV, Meta, Op, MetaOp>
     where
         Key: Eq + Hash + Clone,
         MutV: Mutable<Op> + Clone,
-        Meta: Clone + 'static,
+        Meta: Mutable<MetaOp> + Clone + 'static,
     {
-        let inner = match self.capacity {
-            Some(cap) => Inner::with_capacity(self.meta, cap),
-            None => Inner::new(self.meta),
+        let MapOptions {
+            options,
+            max_entries,
+            indexes,
+            order,
+            aggregates,
+        } = self;
+        let mut inner = match options.capacity {
+            Some(cap) => Inner::with_capacity(options.meta, cap),
+            None => Inner::new(options.meta),
         };
+        inner.indexes = indexes;
+        inner.order = order;
+        inner.aggregates = aggregates;
+
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        if let Some((max_entries, policy)) = max_entries {
+            inner.eviction = Some(Eviction::new(max_entries, policy, Arc::clone(&evicted)));
+        }
+
+        let least_recently_read =
+            matches!(max_entries, Some((_, EvictionPolicy::LeastRecentlyRead)));
+        if options.access_tracking || least_recently_read {
+            inner.epoch = Some(Arc::new(AtomicU64::new(0)));
+        }
+        let epoch = inner.epoch.clone();
 
         // Safety:
         // We must call new_from_inner so that the HashMap is cloned from left to right on initiation
         // (Two calls to HashMap::new will have subtly different hashing behaviour)
         let (mut w, r) = left_right::new_from_empty(inner);
+
+        // left_right's first publish only swaps the two copies, leaving every operation in the
+        // oplog to be applied by the publish after it. Publish the empty map straight away so
+        // that the caller's first publish applies their operations and resolves their outcomes,
+        // like every publish after it. Readers still see an empty map until `MarkReady` is
+        // published.
+        w.publish();
         w.append(Operation::MarkReady);
 
-        (WriteHandle::new(w), ReadHandle::new(r))
+        (WriteHandle::new(w, evicted, epoch), ReadHandle::new(r))
     }
 }
 
-pub fn new<Key, MutV, RefV, Op>() -> (
-    WriteHandle<Key, MutV, RefV, (), Op>,
-    ReadHandle<Key, MutV, RefV, ()>,
-)
+pub fn new<Key, MutV, RefV, Op>() -> Handles<Key, MutV, RefV, (), Op>
 where
     Key: StableHashEq + Clone,
     MutV: Mutable<Op> + Clone,
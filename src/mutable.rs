@@ -12,6 +12,12 @@ pub trait Mutable<O>: Clone {
     /// Similar to left_right's Absorb trait. See that for
     /// details.
     ///
+    /// This is the last time the operation is seen, so it is
+    /// passed by value. The default forwards to `mutate_first`,
+    /// which must clone any payload out of the operation;
+    /// override it to move the payload in instead, or see
+    /// [`OwnedOp`].
+    ///
     /// # Safety
     /// Implementations of this function must be deterministic,
     /// otherwise state may become out of sync in the left and
@@ -30,3 +36,56 @@ where
 
     fn mutate_second(&mut self, _: ()) {}
 }
+
+/// An operation which is applied by consuming it, for operations carrying large payloads.
+///
+/// Wrap it in [`Moving`] to use it as a [`Mutable`] operation. The first copy of the map is
+/// then mutated with a clone of the operation, and the second has the operation itself moved
+/// into it, so the payload is cloned once rather than twice.
+///
+/// ```
+/// use sevmap::muts::{Moving, OwnedOp};
+///
+/// #[derive(Clone)]
+/// struct Append(Vec<u8>);
+///
+/// impl OwnedOp<Vec<u8>> for Append {
+///     fn apply(self, target: &mut Vec<u8>) {
+///         let mut payload = self.0;
+///         target.append(&mut payload);
+///     }
+/// }
+///
+/// let (mut w, r) = sevmap::new::<char, Vec<u8>, (), Moving<Append>>();
+/// w.insert('a', (), vec![1]);
+/// w.mutate('a', Moving(Append(vec![2, 3])));
+/// w.publish();
+/// assert_eq!(r.get(&'a').unwrap().mut_v(), &[1, 2, 3]);
+/// ```
+pub trait OwnedOp<T>: Clone {
+    /// Apply this operation to `target`.
+    ///
+    /// # Safety
+    /// Implementations of this function must be deterministic,
+    /// otherwise state may become out of sync in the left and
+    /// right maps.
+    fn apply(self, target: &mut T);
+}
+
+/// Use an [`OwnedOp`] as a [`Mutable`] operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Moving<O>(pub O);
+
+impl<T, O> Mutable<Moving<O>> for T
+where
+    T: Clone,
+    O: OwnedOp<T>,
+{
+    fn mutate_first(&mut self, operation: &mut Moving<O>) {
+        operation.0.clone().apply(self);
+    }
+
+    fn mutate_second(&mut self, operation: Moving<O>) {
+        operation.0.apply(self);
+    }
+}
@@ -0,0 +1,47 @@
+//! A map whose values are entirely mutable in place.
+//!
+//! This is a thin facade over a map with no immutable part, that is with a `RefV` of `()`,
+//! which hides the split between the two parts of each value. Its handles are those of the
+//! [`facade`] module, with values kept in the mutable part.
+//!
+//! Since `()` is zero-sized and has nothing to drop, inserts and removes skip the aliasing that
+//! lets the two copies of other maps share and then safely drop an immutable part. Every value is
+//! still cloned into each copy.
+
+use crate::{
+    MapOptions, Options,
+    facade::{self, MutableOnly},
+    mutable::Mutable,
+    stable_hash_eq::StableHashEq,
+};
+
+/// A write handle to a mutable-only map, see the [module documentation](self).
+pub type WriteHandle<Key, V, Op, Meta = ()> = facade::WriteHandle<MutableOnly, Key, V, Meta, Op>;
+
+/// A read handle to a mutable-only map, see the [module documentation](self).
+pub type ReadHandle<Key, V, Meta = ()> = facade::ReadHandle<MutableOnly, Key, V, Meta>;
+
+/// A guard on one published version of a mutable-only map, see [`ReadHandle::enter`].
+pub type MapReadRef<'rh, Key, V, Meta = ()> = facade::MapReadRef<'rh, MutableOnly, Key, V, Meta>;
+
+/// Create an empty mutable-only map, and construct the read and write handles used to access it.
+pub fn new<Key, V, Op>() -> (WriteHandle<Key, V, Op>, ReadHandle<Key, V>)
+where
+    Key: StableHashEq + Clone,
+    V: Mutable<Op>,
+{
+    with_options(Options::default())
+}
+
+/// Create a mutable-only map from `options`, either plain [`Options`] or [`MapOptions`], and
+/// construct the read and write handles used to access it.
+pub fn with_options<Key, V, Op, Meta>(
+    options: impl Into<MapOptions<Key, V, (), Meta>>,
+) -> facade::Handles<MutableOnly, Key, V, Meta, Op>
+where
+    Key: StableHashEq + Clone,
+    V: Mutable<Op>,
+    Meta: Clone + 'static,
+{
+    facade::with_options(options.into())
+}
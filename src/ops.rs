@@ -0,0 +1,286 @@
+//! Ready-made operations for common shapes of mutable value.
+//!
+//! Every operation here is deterministic, and leaves the value untouched rather than panicking
+//! when it cannot be applied, since a panic while absorbing an operation would leave the two
+//! copies of the map out of step. Operations carrying a payload clone it into the first copy and
+//! move it into the second.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::mutable::Mutable;
+
+/// An operation on an integer counter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Counter<T> {
+    /// Add to the counter, wrapping on overflow
+    Add(T),
+    /// Subtract from the counter, wrapping on overflow
+    Sub(T),
+    /// Add to the counter, saturating at its maximum
+    SaturatingAdd(T),
+    /// Subtract from the counter, saturating at its minimum
+    SaturatingSub(T),
+    /// Replace the counter
+    Set(T),
+    /// Lower the counter to at most the given value
+    Min(T),
+    /// Raise the counter to at least the given value
+    Max(T),
+}
+
+macro_rules! impl_counter {
+    ($($t:ty),*) => {
+        $(
+            impl Mutable<Counter<$t>> for $t {
+                fn mutate_first(&mut self, operation: &mut Counter<$t>) {
+                    *self = match *operation {
+                        Counter::Add(v) => self.wrapping_add(v),
+                        Counter::Sub(v) => self.wrapping_sub(v),
+                        Counter::SaturatingAdd(v) => self.saturating_add(v),
+                        Counter::SaturatingSub(v) => self.saturating_sub(v),
+                        Counter::Set(v) => v,
+                        Counter::Min(v) => (*self).min(v),
+                        Counter::Max(v) => (*self).max(v),
+                    };
+                }
+            }
+        )*
+    };
+}
+
+impl_counter!(
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize
+);
+
+/// An operation on a [`Vec`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VecOp<T> {
+    Push(T),
+    /// Move every element of the payload onto the end of the vector
+    Append(Vec<T>),
+    Pop,
+    /// Shorten the vector to the given length, if it is longer
+    Truncate(usize),
+    /// Replace the element at the given index, if there is one
+    SetAt(usize, T),
+    Clear,
+}
+
+impl<T> Mutable<VecOp<T>> for Vec<T>
+where
+    T: Clone,
+{
+    fn mutate_first(&mut self, operation: &mut VecOp<T>) {
+        match *operation {
+            VecOp::Push(ref v) => self.push(v.clone()),
+            VecOp::Append(ref vs) => self.extend_from_slice(vs),
+            VecOp::SetAt(i, ref v) => {
+                if let Some(slot) = self.get_mut(i) {
+                    *slot = v.clone();
+                }
+            }
+            _ => apply_vec(self, operation),
+        }
+    }
+
+    fn mutate_second(&mut self, operation: VecOp<T>) {
+        match operation {
+            VecOp::Push(v) => self.push(v),
+            VecOp::Append(mut vs) => self.append(&mut vs),
+            VecOp::SetAt(i, v) => {
+                if let Some(slot) = self.get_mut(i) {
+                    *slot = v;
+                }
+            }
+            operation => apply_vec(self, &operation),
+        }
+    }
+}
+
+/// Apply the operations which carry no payload
+fn apply_vec<T>(vec: &mut Vec<T>, operation: &VecOp<T>) {
+    match *operation {
+        VecOp::Pop => {
+            vec.pop();
+        }
+        VecOp::Truncate(len) => vec.truncate(len),
+        VecOp::Clear => vec.clear(),
+        VecOp::Push(_) | VecOp::Append(_) | VecOp::SetAt(..) => {
+            unreachable!("operation carries a payload")
+        }
+    }
+}
+
+/// An operation on a [`BTreeSet`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SetOp<T> {
+    Insert(T),
+    Remove(T),
+    Clear,
+}
+
+impl<T> Mutable<SetOp<T>> for BTreeSet<T>
+where
+    T: Ord + Clone,
+{
+    fn mutate_first(&mut self, operation: &mut SetOp<T>) {
+        match *operation {
+            SetOp::Insert(ref v) => {
+                self.insert(v.clone());
+            }
+            SetOp::Remove(ref v) => {
+                self.remove(v);
+            }
+            SetOp::Clear => self.clear(),
+        }
+    }
+
+    fn mutate_second(&mut self, operation: SetOp<T>) {
+        match operation {
+            SetOp::Insert(v) => {
+                self.insert(v);
+            }
+            SetOp::Remove(v) => {
+                self.remove(&v);
+            }
+            SetOp::Clear => self.clear(),
+        }
+    }
+}
+
+/// An operation on a [`BTreeMap`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapOp<K, V> {
+    Insert(K, V),
+    Remove(K),
+    Clear,
+}
+
+impl<K, V> Mutable<MapOp<K, V>> for BTreeMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    fn mutate_first(&mut self, operation: &mut MapOp<K, V>) {
+        match *operation {
+            MapOp::Insert(ref k, ref v) => {
+                self.insert(k.clone(), v.clone());
+            }
+            MapOp::Remove(ref k) => {
+                self.remove(k);
+            }
+            MapOp::Clear => self.clear(),
+        }
+    }
+
+    fn mutate_second(&mut self, operation: MapOp<K, V>) {
+        match operation {
+            MapOp::Insert(k, v) => {
+                self.insert(k, v);
+            }
+            MapOp::Remove(k) => {
+                self.remove(&k);
+            }
+            MapOp::Clear => self.clear(),
+        }
+    }
+}
+
+/// An operation on an [`Option`], whose contents are mutated with operations of type `O`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionOp<T, O = ()> {
+    /// Replace the option with `Some`
+    Set(T),
+    /// Set the option to `Some` only if it is `None`
+    GetOrInsert(T),
+    /// Replace the option with `None`
+    Clear,
+    /// Mutate the contents of the option, if it is `Some`
+    Mutate(O),
+}
+
+impl<T, O> Mutable<OptionOp<T, O>> for Option<T>
+where
+    T: Mutable<O>,
+{
+    fn mutate_first(&mut self, operation: &mut OptionOp<T, O>) {
+        match *operation {
+            OptionOp::Set(ref v) => *self = Some(v.clone()),
+            OptionOp::GetOrInsert(ref v) => {
+                self.get_or_insert_with(|| v.clone());
+            }
+            OptionOp::Clear => *self = None,
+            OptionOp::Mutate(ref mut operation) => {
+                if let Some(v) = self {
+                    v.mutate_first(operation);
+                }
+            }
+        }
+    }
+
+    fn mutate_second(&mut self, operation: OptionOp<T, O>) {
+        match operation {
+            OptionOp::Set(v) => *self = Some(v),
+            OptionOp::GetOrInsert(v) => {
+                self.get_or_insert(v);
+            }
+            OptionOp::Clear => *self = None,
+            OptionOp::Mutate(operation) => {
+                if let Some(v) = self {
+                    v.mutate_second(operation);
+                }
+            }
+        }
+    }
+}
+
+/// An operation defined by a closure, for one-off mutations which do not merit an operation type
+/// of their own.
+///
+/// ```
+/// use sevmap::muts::FnOp;
+///
+/// #[derive(Clone)]
+/// struct Stats {
+///     count: u64,
+/// }
+///
+/// let (mut w, r) = sevmap::new::<char, Stats, (), FnOp<Stats>>();
+/// w.insert('a', (), Stats { count: 0 });
+/// w.mutate('a', FnOp::new(|v: &mut Stats| v.count += 1));
+/// w.publish();
+/// assert_eq!(r.get(&'a').unwrap().mut_v().count, 1);
+/// ```
+///
+/// The closure is called once on each copy of the map, so it must be deterministic: given equal
+/// values it must leave them equal. It must not depend on anything which could change between
+/// the two calls, such as the clock, a random number generator or shared mutable state, and it
+/// must not panic.
+#[derive(Clone)]
+pub struct FnOp<T>(Arc<dyn Fn(&mut T) + Send + Sync>);
+
+impl<T> FnOp<T> {
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&mut T) + Send + Sync + 'static,
+    {
+        FnOp(Arc::new(f))
+    }
+}
+
+impl<T> fmt::Debug for FnOp<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FnOp").finish_non_exhaustive()
+    }
+}
+
+impl<T> Mutable<FnOp<T>> for T
+where
+    T: Clone,
+{
+    fn mutate_first(&mut self, operation: &mut FnOp<T>) {
+        (operation.0)(self);
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::Bound;
+
+/// The keys of a map in ascending order, kept only if requested with
+/// [`MapOptions::with_ordered_keys`](crate::MapOptions::with_ordered_keys). One per copy, see
+/// [`Inner`](crate::inner::Inner).
+pub(crate) struct KeyOrder<Key> {
+    keys: Option<Box<dyn AnyKeyOrder<Key>>>,
+}
+
+/// A set of keys in ascending order, with the `Ord` bound on its keys erased
+trait AnyKeyOrder<Key>: Send + Sync {
+    fn insert(&mut self, key: &Key);
+    fn remove(&mut self, key: &Key);
+    fn clear(&mut self);
+    /// The keys strictly after `last`, or every key if `last` is `None`
+    fn after<'a>(&'a self, last: Option<&Key>) -> Box<dyn Iterator<Item = &'a Key> + 'a>;
+    /// An empty order over the same key type
+    fn empty(&self) -> Box<dyn AnyKeyOrder<Key>>;
+}
+
+impl<Key> AnyKeyOrder<Key> for BTreeSet<Key>
+where
+    Key: Ord + Clone + Send + Sync + 'static,
+{
+    fn insert(&mut self, key: &Key) {
+        BTreeSet::insert(self, key.clone());
+    }
+
+    fn remove(&mut self, key: &Key) {
+        BTreeSet::remove(self, key);
+    }
+
+    fn clear(&mut self) {
+        BTreeSet::clear(self);
+    }
+
+    fn after<'a>(&'a self, last: Option<&Key>) -> Box<dyn Iterator<Item = &'a Key> + 'a> {
+        match last {
+            Some(last) => Box::new(self.range((Bound::Excluded(last), Bound::Unbounded))),
+            None => Box::new(self.iter()),
+        }
+    }
+
+    fn empty(&self) -> Box<dyn AnyKeyOrder<Key>> {
+        Box::new(BTreeSet::new())
+    }
+}
+
+impl<Key> KeyOrder<Key> {
+    pub(crate) fn new() -> Self {
+        KeyOrder { keys: None }
+    }
+
+    pub(crate) fn enable(&mut self)
+    where
+        Key: Ord + Clone + Send + Sync + 'static,
+    {
+        self.keys = Some(Box::new(BTreeSet::new()));
+    }
+
+    pub(crate) fn insert(&mut self, key: &Key) {
+        if let Some(keys) = self.keys.as_mut() {
+            keys.insert(key);
+        }
+    }
+
+    pub(crate) fn remove(&mut self, key: &Key) {
+        if let Some(keys) = self.keys.as_mut() {
+            keys.remove(key);
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        if let Some(keys) = self.keys.as_mut() {
+            keys.clear();
+        }
+    }
+
+    /// The keys strictly after `last` in ascending order, or `None` if keys are not being kept
+    /// in order.
+    pub(crate) fn after<'a>(
+        &'a self,
+        last: Option<&Key>,
+    ) -> Option<Box<dyn Iterator<Item = &'a Key> + 'a>> {
+        self.keys.as_ref().map(|keys| keys.after(last))
+    }
+}
+
+impl<Key> Clone for KeyOrder<Key> {
+    /// Clone whether keys are kept in order, but none of the keys.
+    fn clone(&self) -> Self {
+        KeyOrder {
+            keys: self.keys.as_ref().map(|keys| keys.empty()),
+        }
+    }
+}
+
+impl<Key> fmt::Debug for KeyOrder<Key> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyOrder")
+            .field("enabled", &self.keys.is_some())
+            .finish()
+    }
+}
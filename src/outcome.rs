@@ -0,0 +1,58 @@
+use std::sync::{Arc, Mutex};
+
+/// The result of an operation which is only known once the operation has been applied to the
+/// map, i.e. after the next call to [`publish`](crate::handles::WriteHandle::publish).
+///
+/// An operation which is never applied never resolves its outcome. That is one dropped by
+/// [`rollback_to`](crate::handles::WriteHandle::rollback_to) or
+/// [`discard_pending`](crate::handles::WriteHandle::discard_pending), or appended inside a
+/// [`transaction`](crate::handles::WriteHandle::transaction) which failed, so do not wait on
+/// [`is_resolved`](Self::is_resolved) for those.
+pub struct Outcome<T> {
+    slot: Arc<Mutex<Option<T>>>,
+}
+
+impl<T> Outcome<T> {
+    pub(crate) fn new() -> Self {
+        Outcome {
+            slot: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Record the result of the operation.
+    pub(crate) fn set(&self, result: T) {
+        *self.slot.lock().unwrap() = Some(result);
+    }
+
+    /// Whether the operation has been applied yet
+    pub fn is_resolved(&self) -> bool {
+        self.slot.lock().unwrap().is_some()
+    }
+
+    /// Take the result of the operation out, or `None` if it has not been applied yet or has
+    /// already been taken.
+    ///
+    /// Unlike [`get`](Self::get) this does not need the result to be `Clone`, but leaves the
+    /// outcome unresolved for every clone of it.
+    pub fn take(&self) -> Option<T> {
+        self.slot.lock().unwrap().take()
+    }
+}
+
+impl<T> Outcome<T>
+where
+    T: Clone,
+{
+    /// Get the result of the operation, or `None` if it has not been applied yet
+    pub fn get(&self) -> Option<T> {
+        self.slot.lock().unwrap().clone()
+    }
+}
+
+impl<T> Clone for Outcome<T> {
+    fn clone(&self) -> Self {
+        Outcome {
+            slot: Arc::clone(&self.slot),
+        }
+    }
+}
@@ -4,7 +4,7 @@ use crate::{
     inner::{Inner, Value},
     read_ref::MapReadRef,
 };
-use std::{borrow::Borrow, hash::Hash};
+use std::{borrow::Borrow, collections::HashMap, hash::Hash};
 
 /// A read handle to a single-valued map
 pub struct ReadHandle<Key, MutV, RefV, Meta>
@@ -57,43 +57,250 @@ where
     }
 
     pub fn is_empty(&self) -> bool {
-        self.enter().map_or(true, |x| x.is_empty())
+        self.enter().is_none_or(|x| x.is_empty())
     }
 
-    fn get_raw<Q: ?Sized>(
+    fn get_raw<Q>(
         &self,
         key: &Q,
     ) -> Option<ReadGuard<'_, Value<MutV, RefV, crate::aliasing::NoDrop>>>
     where
         Key: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: ?Sized + Hash + Eq,
     {
         let inner = self.handle.enter()?;
         if !inner.ready {
             return None;
         }
 
-        ReadGuard::try_map(inner, |inner| inner.data.get(key))
+        ReadGuard::try_map(inner, |inner| {
+            let value = inner.data.get(key).filter(|value| value.is_live())?;
+            value.record_read(inner.epoch.as_deref());
+            Some(value)
+        })
     }
 
     #[inline]
-    pub fn get<'rh, Q: ?Sized>(
+    pub fn get<'rh, Q>(
         &'rh self,
         key: &'_ Q,
     ) -> Option<ReadGuard<'rh, Value<MutV, RefV, crate::aliasing::NoDrop>>>
     where
         Key: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: ?Sized + Hash + Eq,
     {
         // Call borrow here to monomorphise get_raw fewer times
         self.get_raw(key.borrow())
     }
 
-    pub fn contains_key<Q: ?Sized>(&self, key: &Q) -> bool
+    /// Apply `then` to the value for `key`, releasing the read guard as soon as it returns.
+    ///
+    /// Unlike [`ReadHandle::get`], this can never hold up a publish for longer than `then` runs.
+    pub fn get_and<Q, F, R>(&self, key: &Q, then: F) -> Option<R>
     where
         Key: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: ?Sized + Hash + Eq,
+        F: FnOnce(&Value<MutV, RefV, crate::aliasing::NoDrop>) -> R,
     {
-        self.enter().map_or(false, |x| x.contains_key(key))
+        self.get_raw(key.borrow()).map(|value| then(&value))
+    }
+
+    /// Clone both parts of the value for `key` out of the map, as `(ref_v, mut_v)`.
+    pub fn get_cloned<Q>(&self, key: &Q) -> Option<(RefV, MutV)>
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+        RefV: Clone,
+    {
+        self.get_and(key, |value| (value.ref_v().clone(), value.mut_v().clone()))
+    }
+
+    /// Clone the mutable part of the value for `key` out of the map.
+    pub fn get_mut_v_cloned<Q>(&self, key: &Q) -> Option<MutV>
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.get_and(key, |value| value.mut_v().clone())
+    }
+
+    /// Apply `then` to the value for each key in `keys`, resolving all of them against one
+    /// consistent version of the map under a single read guard.
+    ///
+    /// If the map has not been published yet, every key resolves to `None`.
+    pub fn get_many_and<'k, Q, I, F, R>(&self, keys: I, mut then: F) -> Vec<Option<R>>
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq + 'k,
+        I: IntoIterator<Item = &'k Q>,
+        F: FnMut(&Value<MutV, RefV, crate::aliasing::NoDrop>) -> R,
+    {
+        let keys = keys.into_iter();
+        match self.enter() {
+            Some(map) => keys.map(|key| map.get(key).map(&mut then)).collect(),
+            None => keys.map(|_| None).collect(),
+        }
+    }
+
+    /// Clone the values for each key in `keys` out of the map as `(ref_v, mut_v)`, see
+    /// [`ReadHandle::get_many_and`].
+    pub fn get_many<'k, Q, I>(&self, keys: I) -> Vec<Option<(RefV, MutV)>>
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq + 'k,
+        I: IntoIterator<Item = &'k Q>,
+        RefV: Clone,
+    {
+        self.get_many_and(keys, |value| (value.ref_v().clone(), value.mut_v().clone()))
+    }
+
+    /// Clone out the next page of at most `page_size` values, in ascending key order, starting
+    /// strictly after the last key returned through `cursor`.
+    ///
+    /// The read guard is released before this returns, so writers may publish between pages.
+    /// Because every page resumes from the last key rather than from a position in the table,
+    /// each key is returned at most once however the map changes in the meantime: values removed
+    /// before their page is reached are not returned, and values inserted are returned only if
+    /// their key sorts after the cursor. Each page reflects a single published version of the
+    /// map, but different pages may reflect different versions. Before the map is first
+    /// published every page is empty, and the cursor is left where it was.
+    ///
+    /// If the map keeps its keys in order, see
+    /// [`MapOptions::with_ordered_keys`](crate::MapOptions::with_ordered_keys), each page
+    /// resumes straight from the cursor and holds the read guard only while it copies that page
+    /// out. Otherwise every page walks the whole map to find the keys which follow the cursor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `page_size` is zero.
+    pub fn scan(&self, cursor: &mut Cursor<Key>, page_size: usize) -> Vec<(Key, (RefV, MutV))>
+    where
+        Key: Ord,
+        RefV: Clone,
+    {
+        assert!(page_size > 0, "page_size must be positive");
+        let Some(map) = self.enter() else {
+            return Vec::new();
+        };
+
+        let page = match map.guard.order.after(cursor.last.as_ref()) {
+            Some(keys) => {
+                let mut values = keys.filter_map(|key| {
+                    let value = map.guard.data.get(key).filter(|value| value.is_live())?;
+                    Some((key, value))
+                });
+                let page: Vec<_> = values.by_ref().take(page_size).collect();
+                cursor.finished = values.next().is_none();
+                page
+            }
+            None => {
+                let mut page: Vec<_> = map
+                    .iter()
+                    .filter(|(key, _)| cursor.last.as_ref().is_none_or(|last| *key > last))
+                    .collect();
+                cursor.finished = page.len() <= page_size;
+                if !cursor.finished {
+                    page.select_nth_unstable_by(page_size, |(a, _), (b, _)| a.cmp(b));
+                    page.truncate(page_size);
+                }
+                page.sort_unstable_by_key(|(key, _)| *key);
+                page
+            }
+        };
+
+        if let Some((last, _)) = page.last() {
+            cursor.last = Some((*last).clone());
+        }
+        page.into_iter()
+            .map(|(key, value)| {
+                let ref_v = value.ref_v().clone();
+                let mut_v = value.mut_v().clone();
+                (key.clone(), (ref_v, mut_v))
+            })
+            .collect()
+    }
+
+    /// The keys of the `n` values readers have looked up most recently, most recent first.
+    ///
+    /// Returns nothing unless access tracking is enabled, see
+    /// [`Options::with_access_tracking`](crate::Options::with_access_tracking).
+    pub fn hottest(&self, n: usize) -> Vec<Key> {
+        let mut keys = self.by_last_read();
+        keys.reverse();
+        keys.truncate(n);
+        keys
+    }
+
+    /// The keys of the `n` values readers have looked up least recently, least recent first.
+    ///
+    /// Returns nothing unless access tracking is enabled, see
+    /// [`Options::with_access_tracking`](crate::Options::with_access_tracking).
+    pub fn coldest(&self, n: usize) -> Vec<Key> {
+        let mut keys = self.by_last_read();
+        keys.truncate(n);
+        keys
+    }
+
+    fn by_last_read(&self) -> Vec<Key> {
+        let Some(map) = self.enter().filter(|map| map.guard.epoch.is_some()) else {
+            return Vec::new();
+        };
+
+        let mut values: Vec<_> = map.iter().collect();
+        values.sort_by_key(|(_, value)| (value.last_read(), value.stamp()));
+        values.into_iter().map(|(key, _)| key.clone()).collect()
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.enter().is_some_and(|x| x.contains_key(key))
+    }
+
+    /// Clone the currently published map out into an owned [`HashMap`], see
+    /// [`MapReadRef::to_hash_map`]. The map is empty if it has not been published yet.
+    pub fn snapshot(&self) -> HashMap<Key, (RefV, MutV)>
+    where
+        RefV: Clone,
+    {
+        self.enter().map(|x| x.to_hash_map()).unwrap_or_default()
+    }
+}
+
+/// The position of a paginated [`ReadHandle::scan`] through the map
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor<Key> {
+    last: Option<Key>,
+    finished: bool,
+}
+
+impl<Key> Cursor<Key> {
+    /// A cursor positioned before the first key in the map
+    pub fn new() -> Self {
+        Cursor {
+            last: None,
+            finished: false,
+        }
+    }
+
+    /// The last key returned through this cursor, which the next page resumes after
+    pub fn last_key(&self) -> Option<&Key> {
+        self.last.as_ref()
+    }
+
+    /// Whether the last page reached the end of the map.
+    ///
+    /// A finished scan can still be resumed, and returns any values inserted since with a key
+    /// after the cursor.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+impl<Key> Default for Cursor<Key> {
+    fn default() -> Self {
+        Self::new()
     }
 }
@@ -1,6 +1,8 @@
 use std::{borrow::Borrow, collections::HashMap, hash::Hash};
 
 use left_right::ReadGuard;
+#[cfg(feature = "rayon")]
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 
 use crate::inner::{Inner, Value};
 
@@ -49,41 +51,156 @@ where
         }
     }
 
+    /// The number of values in the map.
+    ///
+    /// Like every other read, this leaves out values whose time-to-live has run out, even if they
+    /// have not yet been removed with [`WriteHandle::expire`](crate::handles::WriteHandle::expire).
     pub fn len(&self) -> usize {
-        self.guard.data.len()
+        self.guard.live_len()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.guard.data.is_empty()
+        self.len() == 0
     }
 
     pub fn meta(&self) -> &Meta {
         &self.guard.meta
     }
 
-    pub fn get<Q: ?Sized>(
+    pub fn get<Q>(&'rh self, key: &'_ Q) -> Option<&'rh Value<MutV, RefV, crate::aliasing::NoDrop>>
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let value = self.guard.data.get(key).filter(|value| value.is_live())?;
+        value.record_read(self.guard.epoch.as_deref());
+        Some(value)
+    }
+
+    /// Look up every key in `keys`, all against this one published version of the map.
+    pub fn get_many<'k, Q, I>(
         &'rh self,
-        key: &'_ Q,
-    ) -> Option<&'rh Value<MutV, RefV, crate::aliasing::NoDrop>>
+        keys: I,
+    ) -> Vec<Option<&'rh Value<MutV, RefV, crate::aliasing::NoDrop>>>
     where
         Key: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: ?Sized + Hash + Eq + 'k,
+        I: IntoIterator<Item = &'k Q>,
     {
-        self.guard.data.get(key)
+        keys.into_iter().map(|key| self.get(key)).collect()
     }
 
-    pub fn contains_key<Q: ?Sized>(&self, key: &Q) -> bool
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
     where
         Key: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: ?Sized + Hash + Eq,
     {
-        self.guard.data.contains_key(key)
+        self.guard
+            .data
+            .get(key)
+            .is_some_and(|value| value.is_live())
+    }
+
+    /// Iterate over the values filed under `index_key` in the secondary index called `name`,
+    /// see [`MapOptions::with_index`](crate::MapOptions::with_index).
+    ///
+    /// # Panics
+    ///
+    /// Panics if no index called `name` was registered, or if it is not keyed by `IndexKey`.
+    pub fn by_index<IndexKey>(
+        &self,
+        name: &str,
+        index_key: &IndexKey,
+    ) -> impl Iterator<Item = (&Key, &Value<MutV, RefV, crate::aliasing::NoDrop>)>
+    where
+        IndexKey: 'static,
+    {
+        self.guard
+            .indexes
+            .keys(name, index_key)
+            .into_iter()
+            .flatten()
+            .filter_map(|key| self.guard.data.get_key_value(key))
+            .filter(|(_, value)| value.is_live())
+    }
+
+    /// The current value of the aggregate called `name`, see
+    /// [`MapOptions::with_aggregate`](crate::MapOptions::with_aggregate).
+    ///
+    /// # Panics
+    ///
+    /// Panics if no aggregate called `name` was registered.
+    pub fn aggregate(&self, name: &str) -> Option<i64> {
+        self.guard.aggregates.get(name)
+    }
+
+    /// Clone the map out into an owned [`HashMap`] of `(ref_v, mut_v)` pairs.
+    ///
+    /// To share the immutable part of each value rather than deep-cloning it, store it behind
+    /// an `Arc` in the first place; cloning `RefV` is then just a reference count increment.
+    pub fn to_hash_map(&self) -> HashMap<Key, (RefV, MutV)>
+    where
+        Key: Clone,
+        RefV: Clone,
+    {
+        let mut map = HashMap::with_capacity(self.len());
+        self.collect_into(&mut map);
+        map
+    }
+
+    /// Clone every (key, value) in the map into `collection`, as `(key, (ref_v, mut_v))` pairs.
+    pub fn collect_into<C>(&self, collection: &mut C)
+    where
+        Key: Clone,
+        RefV: Clone,
+        C: Extend<(Key, (RefV, MutV))>,
+    {
+        collection.extend(self.iter().map(|(key, value)| {
+            let ref_v = value.ref_v().clone();
+            let mut_v = value.mut_v().clone();
+            (key.clone(), (ref_v, mut_v))
+        }));
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<Key, MutV, RefV, Meta> MapReadRef<'_, Key, MutV, RefV, Meta>
+where
+    Key: Hash + Eq + Sync,
+    MutV: Clone + Sync,
+    RefV: Sync,
+    Meta: Clone,
+{
+    /// Iterate over all (keys, values) in the map in parallel, with every worker thread sharing
+    /// this one read guard.
+    ///
+    /// References to the entries are gathered up front and then split between the workers, so
+    /// the per-entry work should dominate for this to pay off.
+    ///
+    /// As with [`MapReadRef::iter`], any writer that tries to publish changes will block until
+    /// the iteration has finished.
+    pub fn par_iter(
+        &self,
+    ) -> impl ParallelIterator<Item = (&Key, &Value<MutV, RefV, crate::aliasing::NoDrop>)> {
+        self.guard.data.par_iter().filter(|(_, v)| v.is_live())
+    }
+
+    /// Iterate over all keys in the map in parallel, see [`MapReadRef::par_iter`].
+    pub fn par_keys(&self) -> impl ParallelIterator<Item = &Key> {
+        self.par_iter().map(|(k, _)| k)
+    }
+
+    /// Iterate over all values in the map in parallel, see [`MapReadRef::par_iter`].
+    pub fn par_values(
+        &self,
+    ) -> impl ParallelIterator<Item = &Value<MutV, RefV, crate::aliasing::NoDrop>> {
+        self.par_iter().map(|(_, v)| v)
     }
 }
 
 /// An [`Iterator`] over (keys, values) in the map
 ///
-/// Note: Keeps the read guard alive
+/// Note: Keeps the read guard alive, and skips values whose time-to-live has run out
 pub struct ReadGuardIter<'rg, Key, MutV, RefV>
 where
     MutV: Clone,
@@ -98,13 +215,13 @@ where
     type Item = (&'rg Key, &'rg Value<MutV, RefV, crate::aliasing::NoDrop>);
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next()
+        self.iter.find(|(_, v)| v.is_live())
     }
 }
 
 /// An [`Iterator`] over keys in the map
 ///
-/// Note: Keeps the read guard alive
+/// Note: Keeps the read guard alive, and skips values whose time-to-live has run out
 pub struct ReadGuardKeys<'rg, Key, MutV, RefV>
 where
     MutV: Clone,
@@ -119,13 +236,13 @@ where
     type Item = &'rg Key;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next().map(|(k, _)| k)
+        self.iter.find(|(_, v)| v.is_live()).map(|(k, _)| k)
     }
 }
 
 /// An [`Iterator`] over values in the map
 ///
-/// Note: Keeps the read guard alive
+/// Note: Keeps the read guard alive, and skips values whose time-to-live has run out
 pub struct ReadGuardValues<'rg, Key, MutV, RefV>
 where
     MutV: Clone,
@@ -140,6 +257,6 @@ where
     type Item = &'rg Value<MutV, RefV, crate::aliasing::NoDrop>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next().map(|(_, v)| v)
+        self.iter.find(|(_, v)| v.is_live()).map(|(_, v)| v)
     }
 }
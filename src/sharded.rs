@@ -0,0 +1,422 @@
+//! A map partitioned by key hash across several independent maps, so that writes to different
+//! shards need not wait on each other.
+//!
+//! Each shard is a complete map with its own pair of copies, and is published on its own. A
+//! reader therefore sees each shard as of that shard's last publish: a read spanning several
+//! shards, such as [`ReadHandle::len`], may combine shards published at different times.
+//!
+//! Writes go through a single [`WriteHandle`], which can be shared between threads and locks
+//! only the shard a write is routed to. Alternatively [`WriteHandle::into_shards`] splits it
+//! into one plain write handle per shard, for a thread each.
+//!
+//! Writes to a single key are forwarded to its shard, and writes to the whole map, such as
+//! [`WriteHandle::clear`], to every shard in turn. Savepoints, transactions,
+//! [`rename`](handles::WriteHandle::rename) and [`swap`](handles::WriteHandle::swap) cannot span
+//! shards, so they are only available through the handle of a single shard, see
+//! [`WriteHandle::shard`].
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, RandomState};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use left_right::ReadGuard;
+
+use crate::{
+    MapOptions, Options, handles, inner::Value, mutable::Mutable, outcome::Outcome,
+    read_ref::MapReadRef, stable_hash_eq::StableHashEq,
+};
+
+/// Routes keys to shards by their hash
+#[derive(Debug, Clone)]
+struct Router {
+    hasher: RandomState,
+    shards: usize,
+}
+
+impl Router {
+    fn shard_of<Q>(&self, key: &Q) -> usize
+    where
+        Q: ?Sized + Hash,
+    {
+        (self.hasher.hash_one(key) % self.shards as u64) as usize
+    }
+}
+
+/// The write handle to a single shard
+type Shard<Key, MutV, RefV, Meta, Op, MetaOp> =
+    handles::WriteHandle<Key, MutV, RefV, Meta, Op, MetaOp>;
+
+/// The write handles to every shard, each behind its own lock
+type Shards<Key, MutV, RefV, Meta, Op, MetaOp> =
+    Vec<Mutex<Shard<Key, MutV, RefV, Meta, Op, MetaOp>>>;
+
+/// The write handles to every shard, and a read handle which routes lookups to them
+type Parts<Key, MutV, RefV, Meta, Op, MetaOp> = (
+    Vec<Shard<Key, MutV, RefV, Meta, Op, MetaOp>>,
+    ReadHandle<Key, MutV, RefV, Meta>,
+);
+
+/// The write and read handles to a newly created sharded map
+type Handles<Key, MutV, RefV, Meta, Op, MetaOp = ()> = (
+    WriteHandle<Key, MutV, RefV, Meta, Op, MetaOp>,
+    ReadHandle<Key, MutV, RefV, Meta>,
+);
+
+/// A write handle to a sharded map, see the [module documentation](self).
+pub struct WriteHandle<Key, MutV, RefV, Meta, Op, MetaOp = ()>
+where
+    Key: Eq + Hash + Clone,
+    MutV: Mutable<Op> + Clone,
+    Meta: Mutable<MetaOp> + Clone,
+{
+    shards: Shards<Key, MutV, RefV, Meta, Op, MetaOp>,
+    router: Router,
+}
+
+/// A read handle to a sharded map, see the [module documentation](self).
+pub struct ReadHandle<Key, MutV, RefV, Meta>
+where
+    Key: Eq + Hash + Clone,
+    MutV: Clone,
+    Meta: Clone,
+{
+    shards: Vec<handles::ReadHandle<Key, MutV, RefV, Meta>>,
+    router: Router,
+}
+
+/// Create a map with `shards` shards, and construct the read and write handles used to access
+/// it.
+///
+/// # Panics
+///
+/// Panics if `shards` is zero.
+pub fn new<Key, MutV, RefV, Op>(shards: usize) -> Handles<Key, MutV, RefV, (), Op>
+where
+    Key: StableHashEq + Clone,
+    MutV: Mutable<Op> + Clone,
+{
+    with_options(shards, |_| Options::default())
+}
+
+/// Create a map with `shards` shards, each constructed from the [`Options`] or [`MapOptions`]
+/// that `options` returns for its index.
+///
+/// Options such as [`MapOptions::with_max_entries`] apply to each shard separately.
+///
+/// # Panics
+///
+/// Panics if `shards` is zero.
+pub fn with_options<Key, MutV, RefV, Meta, Op, MetaOp, F, O>(
+    shards: usize,
+    mut options: F,
+) -> Handles<Key, MutV, RefV, Meta, Op, MetaOp>
+where
+    Key: StableHashEq + Clone,
+    MutV: Mutable<Op> + Clone,
+    Meta: Mutable<MetaOp> + Clone + 'static,
+    F: FnMut(usize) -> O,
+    O: Into<MapOptions<Key, MutV, RefV, Meta, MetaOp>>,
+{
+    assert!(shards > 0, "a sharded map needs at least one shard");
+
+    let router = Router {
+        hasher: RandomState::new(),
+        shards,
+    };
+    let (writers, readers) = (0..shards)
+        .map(|shard| {
+            let (w, r) = options(shard).into().construct();
+            (Mutex::new(w), r)
+        })
+        .unzip();
+
+    let w = WriteHandle {
+        shards: writers,
+        router: router.clone(),
+    };
+    let r = ReadHandle {
+        shards: readers,
+        router,
+    };
+    (w, r)
+}
+
+impl<Key, MutV, RefV, Meta, Op, MetaOp> WriteHandle<Key, MutV, RefV, Meta, Op, MetaOp>
+where
+    Key: Eq + Hash + Clone,
+    MutV: Mutable<Op> + Clone,
+    Meta: Mutable<MetaOp> + Clone,
+{
+    /// The number of shards
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// The index of the shard `key` belongs to
+    pub fn shard_of<Q>(&self, key: &Q) -> usize
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash,
+    {
+        self.router.shard_of(key)
+    }
+
+    /// Lock the write handle of the shard at `index`, to write to it directly.
+    ///
+    /// Only keys which [`shard_of`](Self::shard_of) routes to this shard may be written through
+    /// it; readers will not find any others.
+    ///
+    /// Release the guard before writing through any other method of this handle. Methods which
+    /// write to every shard, such as [`publish`](Self::publish), lock each shard in turn, and
+    /// the others lock the shard of their key, so they deadlock on a shard the calling thread
+    /// already holds.
+    pub fn shard(&self, index: usize) -> MutexGuard<'_, Shard<Key, MutV, RefV, Meta, Op, MetaOp>> {
+        self.shards[index].lock().unwrap()
+    }
+
+    /// Lock the write handle of the shard `key` belongs to, see [`shard`](Self::shard).
+    pub fn shard_for<Q>(&self, key: &Q) -> MutexGuard<'_, Shard<Key, MutV, RefV, Meta, Op, MetaOp>>
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash,
+    {
+        self.shard(self.shard_of(key))
+    }
+
+    /// Lock each shard in turn and apply `f` to its write handle.
+    fn for_each_shard(&self, mut f: impl FnMut(&mut Shard<Key, MutV, RefV, Meta, Op, MetaOp>)) {
+        for shard in &self.shards {
+            f(&mut shard.lock().unwrap());
+        }
+    }
+
+    /// Publish the pending operations of every shard, one shard at a time.
+    ///
+    /// This locks every shard, so it deadlocks if the calling thread holds a guard from
+    /// [`shard`](Self::shard) or [`shard_for`](Self::shard_for). The same goes for every other
+    /// method which writes to all shards.
+    pub fn publish(&self) {
+        self.for_each_shard(|shard| shard.publish());
+    }
+
+    /// Whether any shard has operations which have not been published yet
+    pub fn has_pending(&self) -> bool {
+        self.shards
+            .iter()
+            .any(|shard| shard.lock().unwrap().has_pending())
+    }
+
+    /// Discard the pending operations of every shard, see
+    /// [`handles::WriteHandle::discard_pending`].
+    pub fn discard_pending(&self) {
+        self.for_each_shard(|shard| {
+            shard.discard_pending();
+        });
+    }
+
+    /// Take the keys of the values evicted from every shard since they were last taken, see
+    /// [`handles::WriteHandle::take_evicted`].
+    pub fn take_evicted(&self) -> Vec<Key> {
+        let mut evicted = Vec::new();
+        self.for_each_shard(|shard| evicted.extend(shard.take_evicted()));
+        evicted
+    }
+
+    /// Set the meta of every shard, see [`handles::WriteHandle::set_meta`].
+    pub fn set_meta(&self, meta: Meta) {
+        self.for_each_shard(|shard| shard.set_meta(meta.clone()));
+    }
+
+    /// Update the meta of every shard in place, see [`handles::WriteHandle::mutate_meta`].
+    pub fn mutate_meta(&self, op: MetaOp)
+    where
+        MetaOp: Clone,
+    {
+        self.for_each_shard(|shard| {
+            shard.mutate_meta(op.clone());
+        });
+    }
+
+    pub fn insert(&self, k: Key, ref_v: RefV, mut_v: MutV) {
+        self.shard_for(&k).insert(k, ref_v, mut_v);
+    }
+
+    /// See [`handles::WriteHandle::insert_with_ttl`].
+    pub fn insert_with_ttl(&self, k: Key, ref_v: RefV, mut_v: MutV, ttl: Duration) {
+        self.shard_for(&k).insert_with_ttl(k, ref_v, mut_v, ttl);
+    }
+
+    /// Remove every value whose time-to-live had run out by `now` from every shard, see
+    /// [`handles::WriteHandle::expire`].
+    pub fn expire(&self, now: Instant) {
+        self.for_each_shard(|shard| {
+            shard.expire(now);
+        });
+    }
+
+    pub fn mutate(&self, k: Key, op: Op) {
+        self.shard_for(&k).mutate(k, op);
+    }
+
+    /// See [`handles::WriteHandle::compare_and_mutate`].
+    pub fn compare_and_mutate(&self, k: Key, expected: MutV, op: Op) -> Outcome<bool>
+    where
+        MutV: PartialEq,
+    {
+        self.shard_for(&k).compare_and_mutate(k, expected, op)
+    }
+
+    /// See [`handles::WriteHandle::compare_and_insert`].
+    pub fn compare_and_insert(
+        &self,
+        k: Key,
+        expected: Option<MutV>,
+        ref_v: RefV,
+        mut_v: MutV,
+    ) -> Outcome<bool>
+    where
+        MutV: PartialEq,
+    {
+        self.shard_for(&k)
+            .compare_and_insert(k, expected, ref_v, mut_v)
+    }
+
+    pub fn remove(&self, k: Key) {
+        self.shard_for(&k).remove(k);
+    }
+
+    /// See [`handles::WriteHandle::remove_and_take`].
+    pub fn remove_and_take(&self, k: Key) -> Outcome<Option<(RefV, MutV)>> {
+        self.shard_for(&k).remove_and_take(k)
+    }
+
+    /// Clear every shard, one shard at a time.
+    pub fn clear(&self) {
+        self.for_each_shard(|shard| {
+            shard.clear();
+        });
+    }
+
+    /// Split into the write handles of each shard, in order, alongside a read handle which
+    /// routes lookups to them.
+    ///
+    /// Only keys which [`ReadHandle::shard_of`] routes to a shard may be written through its
+    /// handle; readers will not find any others.
+    pub fn into_shards(self) -> Parts<Key, MutV, RefV, Meta, Op, MetaOp> {
+        let shards: Vec<_> = self
+            .shards
+            .into_iter()
+            .map(|shard| shard.into_inner().unwrap())
+            .collect();
+        let r = ReadHandle {
+            shards: shards.iter().map(|w| (**w).clone()).collect(),
+            router: self.router,
+        };
+        (shards, r)
+    }
+}
+
+impl<Key, MutV, RefV, Meta> Clone for ReadHandle<Key, MutV, RefV, Meta>
+where
+    Key: Eq + Hash + Clone,
+    MutV: Clone,
+    Meta: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            shards: self.shards.clone(),
+            router: self.router.clone(),
+        }
+    }
+}
+
+impl<Key, MutV, RefV, Meta> ReadHandle<Key, MutV, RefV, Meta>
+where
+    Key: Eq + Hash + Clone,
+    MutV: Clone,
+    Meta: Clone,
+{
+    /// The number of shards
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// The index of the shard `key` belongs to
+    pub fn shard_of<Q>(&self, key: &Q) -> usize
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash,
+    {
+        self.router.shard_of(key)
+    }
+
+    /// The read handle of the shard at `index`
+    pub fn shard(&self, index: usize) -> &handles::ReadHandle<Key, MutV, RefV, Meta> {
+        &self.shards[index]
+    }
+
+    pub fn get<'rh, Q>(
+        &'rh self,
+        key: &'_ Q,
+    ) -> Option<ReadGuard<'rh, Value<MutV, RefV, crate::aliasing::NoDrop>>>
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.shards[self.shard_of(key)].get(key)
+    }
+
+    /// See [`handles::ReadHandle::get_and`].
+    pub fn get_and<Q, F, R>(&self, key: &Q, then: F) -> Option<R>
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+        F: FnOnce(&Value<MutV, RefV, crate::aliasing::NoDrop>) -> R,
+    {
+        self.shards[self.shard_of(key)].get_and(key, then)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.shards[self.shard_of(key)].contains_key(key)
+    }
+
+    /// The number of values across every shard
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|shard| shard.is_empty())
+    }
+
+    /// Enter every shard which has been published, in order, to iterate across all of them.
+    ///
+    /// Be careful with this function! While the guards are held, any writer that tries to
+    /// publish changes to one of these shards will block waiting on this reader to finish.
+    pub fn enter_all(&self) -> Vec<MapReadRef<'_, Key, MutV, RefV, Meta>> {
+        self.shards
+            .iter()
+            .filter_map(|shard| shard.enter())
+            .collect()
+    }
+
+    /// Clone every shard out into one owned [`HashMap`], see [`handles::ReadHandle::snapshot`].
+    pub fn snapshot(&self) -> HashMap<Key, (RefV, MutV)>
+    where
+        RefV: Clone,
+    {
+        let mut map = HashMap::new();
+        for shard in &self.shards {
+            if let Some(shard) = shard.enter() {
+                shard.collect_into(&mut map);
+            }
+        }
+        map
+    }
+}
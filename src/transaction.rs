@@ -0,0 +1,303 @@
+use std::{borrow::Borrow, collections::HashMap, hash::Hash, time::Instant};
+
+use crate::{
+    inner::{Operation, Value},
+    mutable::Mutable,
+    read_ref::MapReadRef,
+};
+
+/// A batch of operations which is validated against the map before being published, see
+/// [`WriteHandle::transaction`](crate::handles::WriteHandle::transaction).
+///
+/// Reads through a transaction observe the last published state of the map with every pending
+/// operation applied on top, including those appended before the transaction began.
+pub struct Transaction<'w, Key, MutV, RefV, Meta, Op, MetaOp>
+where
+    Key: Eq + Hash + Clone,
+    MutV: Mutable<Op> + Clone,
+    Meta: Mutable<MetaOp> + Clone,
+    Op: Clone,
+{
+    view: Option<MapReadRef<'w, Key, MutV, RefV, Meta>>,
+    pending: &'w mut Vec<Operation<Key, MutV, RefV, Meta, Op, MetaOp>>,
+    /// The staged state of every key touched by a pending operation; `None` if it is absent
+    staged: HashMap<Key, Option<Staged<Key, MutV>>>,
+    /// Whether a pending `Clear` hides every published entry not in `staged`
+    cleared: bool,
+    /// The latest instant a pending `Expire` removes published entries up to
+    expired: Option<Instant>,
+}
+
+struct Staged<Key, MutV> {
+    source: Source<Key>,
+    mut_v: MutV,
+    deadline: Option<Instant>,
+}
+
+impl<Key, MutV> Staged<Key, MutV> {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.deadline.is_some_and(|deadline| deadline <= now)
+    }
+
+    fn is_live(&self) -> bool {
+        self.deadline.is_none() || !self.is_expired(Instant::now())
+    }
+}
+
+/// Where the immutable part of a staged entry lives
+enum Source<Key> {
+    /// In the published map, under the given key
+    Published(Key),
+    /// In the pending insert at the given index
+    Pending(usize),
+}
+
+impl<'w, Key, MutV, RefV, Meta, Op, MetaOp> Transaction<'w, Key, MutV, RefV, Meta, Op, MetaOp>
+where
+    Key: Eq + Hash + Clone,
+    MutV: Mutable<Op> + Clone,
+    Meta: Mutable<MetaOp> + Clone,
+    Op: Clone,
+{
+    pub(crate) fn new(
+        view: Option<MapReadRef<'w, Key, MutV, RefV, Meta>>,
+        pending: &'w mut Vec<Operation<Key, MutV, RefV, Meta, Op, MetaOp>>,
+    ) -> Self {
+        let mut tx = Transaction {
+            view,
+            pending,
+            staged: HashMap::new(),
+            cleared: false,
+            expired: None,
+        };
+        for index in 0..tx.pending.len() {
+            tx.stage(index);
+        }
+        tx
+    }
+
+    /// Look `key` up in the published map, if no pending operation has removed it
+    fn published<Q>(&self, key: &Q) -> Option<&Value<MutV, RefV, crate::aliasing::NoDrop>>
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        published(&self.view, self.cleared, self.expired, key)
+    }
+
+    /// Look `key` up as it will be once every pending operation is applied
+    fn lookup<Q>(&self, key: &Q) -> Option<(&RefV, &MutV)>
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let staged = match self.staged.get(key) {
+            Some(staged) => staged.as_ref()?,
+            None => {
+                let value = self.published(key).filter(|value| value.is_live())?;
+                return Some((value.ref_v(), value.mut_v()));
+            }
+        };
+        if !staged.is_live() {
+            return None;
+        }
+
+        let ref_v = match staged.source {
+            Source::Published(ref source) => {
+                self.view.as_ref()?.guard.data.get::<Key>(source)?.ref_v()
+            }
+            Source::Pending(index) => match self.pending[index] {
+                Operation::Insert(_, ref value)
+                | Operation::CompareAndInsert(_, _, ref value, _) => value.ref_v(),
+                _ => unreachable!("staged from a pending insert"),
+            },
+        };
+        Some((ref_v, &staged.mut_v))
+    }
+
+    /// Apply the pending operation at `index` to the staged state.
+    fn stage(&mut self, index: usize) {
+        let Transaction {
+            ref view,
+            ref pending,
+            ref mut staged,
+            ref mut cleared,
+            ref mut expired,
+        } = *self;
+
+        match pending[index] {
+            Operation::Insert(ref key, ref value) => {
+                let entry = Staged {
+                    source: Source::Pending(index),
+                    mut_v: value.mut_v.clone(),
+                    deadline: value.deadline(),
+                };
+                staged.insert(key.clone(), Some(entry));
+            }
+            Operation::Remove(ref key) | Operation::RemoveAndTake(ref key, _) => {
+                staged.insert(key.clone(), None);
+            }
+            Operation::Rename(ref old, ref new) => {
+                if let Some(moved) = entry(staged, *cleared, *expired, view, old).take() {
+                    staged.insert(new.clone(), Some(moved));
+                }
+            }
+            Operation::Swap(ref k1, ref k2) => {
+                if k1 != k2 {
+                    let v1 = entry(staged, *cleared, *expired, view, k1).take();
+                    let v2 = entry(staged, *cleared, *expired, view, k2).take();
+                    staged.insert(k1.clone(), v2);
+                    staged.insert(k2.clone(), v1);
+                }
+            }
+            Operation::Clear => {
+                staged.clear();
+                *cleared = true;
+            }
+            Operation::Expire(now) => {
+                // An expired staged entry hides any published value just as a staged remove does
+                for entry in staged.values_mut() {
+                    if entry.as_ref().is_some_and(|entry| entry.is_expired(now)) {
+                        *entry = None;
+                    }
+                }
+                *expired = Some(expired.map_or(now, |expired| expired.max(now)));
+            }
+            Operation::SetMeta(_) | Operation::MutateMeta(_) | Operation::MarkReady => {}
+            Operation::Mutate(ref key, ref operation) => {
+                if let Some(entry) = entry(staged, *cleared, *expired, view, key) {
+                    Mutable::mutate_second(&mut entry.mut_v, operation.clone());
+                }
+            }
+            Operation::CompareAndMutate(ref key, ref expected, ref operation, _) => {
+                let entry = entry(staged, *cleared, *expired, view, key)
+                    .as_mut()
+                    .filter(|entry| entry.is_live() && expected.matches(Some(&entry.mut_v)));
+
+                if let Some(entry) = entry {
+                    Mutable::mutate_second(&mut entry.mut_v, operation.clone());
+                }
+            }
+            Operation::CompareAndInsert(ref key, ref expected, ref value, _) => {
+                let entry = entry(staged, *cleared, *expired, view, key);
+                let live = entry.as_ref().filter(|entry| entry.is_live());
+                if expected.matches(live.map(|entry| &entry.mut_v)) {
+                    *entry = Some(Staged {
+                        source: Source::Pending(index),
+                        mut_v: value.mut_v.clone(),
+                        deadline: value.deadline(),
+                    });
+                }
+            }
+        }
+    }
+
+    fn append_op(&mut self, op: Operation<Key, MutV, RefV, Meta, Op, MetaOp>) -> &mut Self {
+        self.pending.push(op);
+        self.stage(self.pending.len() - 1);
+        self
+    }
+
+    pub fn insert(&mut self, k: Key, ref_v: RefV, mut_v: MutV) -> &mut Self {
+        let value = Value::new(ref_v, mut_v, None);
+
+        self.append_op(Operation::Insert(k, value))
+    }
+
+    pub fn mutate(&mut self, k: Key, op: Op) -> &mut Self {
+        self.append_op(Operation::Mutate(k, op))
+    }
+
+    pub fn remove(&mut self, k: Key) -> &mut Self {
+        self.append_op(Operation::Remove(k))
+    }
+
+    /// See [`WriteHandle::rename`](crate::handles::WriteHandle::rename).
+    pub fn rename(&mut self, old: Key, new: Key) -> &mut Self {
+        self.append_op(Operation::Rename(old, new))
+    }
+
+    /// See [`WriteHandle::swap`](crate::handles::WriteHandle::swap).
+    pub fn swap(&mut self, k1: Key, k2: Key) -> &mut Self {
+        self.append_op(Operation::Swap(k1, k2))
+    }
+
+    pub fn clear(&mut self) -> &mut Self {
+        self.append_op(Operation::Clear)
+    }
+
+    /// Whether `key` will be present once the transaction is published
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.lookup(key).is_some()
+    }
+
+    /// Read the mutable state of `key`, as it will be once the transaction is published
+    pub fn mut_v<Q>(&self, key: &Q) -> Option<&MutV>
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.lookup(key).map(|(_, mut_v)| mut_v)
+    }
+
+    /// Read the immutable state of `key`, as it will be once the transaction is published
+    pub fn ref_v<Q>(&self, key: &Q) -> Option<&RefV>
+    where
+        Key: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.lookup(key).map(|(ref_v, _)| ref_v)
+    }
+}
+
+/// Look `key` up in the published map, unless a pending `Clear` or `Expire` has removed it.
+fn published<'v, Key, MutV, RefV, Meta, Q>(
+    view: &'v Option<MapReadRef<'_, Key, MutV, RefV, Meta>>,
+    cleared: bool,
+    expired: Option<Instant>,
+    key: &Q,
+) -> Option<&'v Value<MutV, RefV, crate::aliasing::NoDrop>>
+where
+    Key: Eq + Hash + Borrow<Q>,
+    MutV: Clone,
+    Meta: Clone,
+    Q: ?Sized + Hash + Eq,
+{
+    if cleared {
+        return None;
+    }
+
+    let value = view.as_ref()?.guard.data.get(key)?;
+    match expired {
+        Some(now) if value.is_expired(now) => None,
+        _ => Some(value),
+    }
+}
+
+/// Get the staged state of `key`, pulling it in from the published map if this is the first
+/// pending operation to touch it.
+fn entry<'s, Key, MutV, RefV, Meta>(
+    staged: &'s mut HashMap<Key, Option<Staged<Key, MutV>>>,
+    cleared: bool,
+    expired: Option<Instant>,
+    view: &Option<MapReadRef<'_, Key, MutV, RefV, Meta>>,
+    key: &Key,
+) -> &'s mut Option<Staged<Key, MutV>>
+where
+    Key: Eq + Hash + Clone,
+    MutV: Clone,
+    Meta: Clone,
+{
+    staged.entry(key.clone()).or_insert_with(|| {
+        let value = published(view, cleared, expired, key)?;
+        Some(Staged {
+            source: Source::Published(key.clone()),
+            mut_v: value.mut_v.clone(),
+            deadline: value.deadline(),
+        })
+    })
+}
@@ -1,86 +1,354 @@
-use left_right::aliasing::Aliased;
-
 use crate::{
-    inner::{Inner, Operation, Value},
+    inner::{Expected, Inner, Operation, Value},
     mutable::Mutable,
+    outcome::Outcome,
     read::ReadHandle,
+    transaction::Transaction,
+};
+use std::{
+    hash::Hash,
+    ops::Deref,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
 };
-use std::{hash::Hash, ops::Deref};
+
+/// The oplog that a [`WriteHandle`] publishes its operations through
+pub(crate) type OpLog<Key, MutV, RefV, Meta, Op, MetaOp> = left_right::WriteHandle<
+    Inner<Key, MutV, RefV, Meta>,
+    Operation<Key, MutV, RefV, Meta, Op, MetaOp>,
+>;
 
 /// A write handle to a single-valued map
-pub struct WriteHandle<Key, MutV, RefV, Meta, Op>
+pub struct WriteHandle<Key, MutV, RefV, Meta, Op, MetaOp = ()>
 where
     Key: Eq + Hash + Clone,
     MutV: Mutable<Op> + Clone,
-    Meta: Clone,
+    Meta: Mutable<MetaOp> + Clone,
 {
-    write:
-        left_right::WriteHandle<Inner<Key, MutV, RefV, Meta>, Operation<Key, MutV, RefV, Meta, Op>>,
+    write: OpLog<Key, MutV, RefV, Meta, Op, MetaOp>,
     read: ReadHandle<Key, MutV, RefV, Meta>,
+    /// Operations which have not yet been handed to the oplog, and so can still be discarded
+    pending: Vec<Operation<Key, MutV, RefV, Meta, Op, MetaOp>>,
+    /// Number of publishes so far; used to invalidate savepoints
+    generation: usize,
+    /// The length each rollback since the last publish cut the pending operations back to, in
+    /// order; used to invalidate savepoints taken after the point a rollback returned to
+    rollbacks: Vec<usize>,
+    /// Keys evicted by published operations which have not been taken yet
+    evicted: Arc<Mutex<Vec<Key>>>,
+    /// The read epoch, if readers record when they last looked values up
+    epoch: Option<Arc<AtomicU64>>,
+}
+
+/// A position in the pending operations of a [`WriteHandle`], see [`WriteHandle::savepoint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Savepoint {
+    generation: usize,
+    /// The number of rollbacks made before this savepoint was taken
+    rollbacks: usize,
+    len: usize,
 }
 
-impl<Key, MutV, RefV, Meta, Op> WriteHandle<Key, MutV, RefV, Meta, Op>
+impl<Key, MutV, RefV, Meta, Op, MetaOp> WriteHandle<Key, MutV, RefV, Meta, Op, MetaOp>
 where
     Key: Eq + Hash + Clone,
     MutV: Mutable<Op> + Clone,
-    Meta: Clone,
+    Meta: Mutable<MetaOp> + Clone,
 {
     pub(crate) fn new(
-        write: left_right::WriteHandle<
-            Inner<Key, MutV, RefV, Meta>,
-            Operation<Key, MutV, RefV, Meta, Op>,
-        >,
+        write: OpLog<Key, MutV, RefV, Meta, Op, MetaOp>,
+        evicted: Arc<Mutex<Vec<Key>>>,
+        epoch: Option<Arc<AtomicU64>>,
     ) -> Self {
         let read = ReadHandle::new(left_right::ReadHandle::clone(&*write));
 
-        Self { read, write }
+        Self {
+            read,
+            write,
+            pending: Vec::new(),
+            generation: 0,
+            rollbacks: Vec::new(),
+            evicted,
+            epoch,
+        }
     }
 
     pub fn publish(&mut self) {
+        self.publish_pending();
+    }
+
+    /// Take the keys of the values evicted to keep the map within its maximum number of
+    /// entries, see [`MapOptions::with_max_entries`](crate::MapOptions::with_max_entries).
+    ///
+    /// Keys build up across publishes, including those made by
+    /// [`transaction`](Self::transaction), until they are taken.
+    pub fn take_evicted(&mut self) -> Vec<Key> {
+        std::mem::take(&mut *self.evicted.lock().unwrap())
+    }
+
+    fn publish_pending(&mut self) {
+        self.write.extend(self.pending.drain(..));
         self.write.publish();
+        self.generation += 1;
+        self.rollbacks.clear();
+
+        if let Some(epoch) = self.epoch.as_ref() {
+            epoch.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     pub fn has_pending(&self) -> bool {
-        self.write.has_pending_operations()
+        !self.pending.is_empty() || self.write.has_pending_operations()
     }
 
-    fn append_op(&mut self, op: Operation<Key, MutV, RefV, Meta, Op>) -> &mut Self {
-        self.write.append(op);
+    fn append_op(&mut self, op: Operation<Key, MutV, RefV, Meta, Op, MetaOp>) -> &mut Self {
+        self.pending.push(op);
         self
     }
 
+    /// Mark the current position in the pending (unpublished) operations, so that any
+    /// operations appended after it can later be dropped with [`rollback_to`](Self::rollback_to).
+    ///
+    /// A savepoint is only valid until the next call to [`publish`](Self::publish), or until a
+    /// rollback to a savepoint taken before it.
+    pub fn savepoint(&self) -> Savepoint {
+        Savepoint {
+            generation: self.generation,
+            rollbacks: self.rollbacks.len(),
+            len: self.pending.len(),
+        }
+    }
+
+    /// Drop every operation appended since `savepoint` was taken, without applying them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the map has been published since `savepoint` was taken, if a rollback to an
+    /// earlier savepoint has already dropped the operations it refers to, or if `savepoint` was
+    /// taken from another handle and refers to operations this one does not have.
+    pub fn rollback_to(&mut self, savepoint: Savepoint) -> &mut Self {
+        // A rollback since the savepoint was taken which cut back to before it dropped
+        // operations it covers, which later operations may since have replaced
+        let valid = savepoint.generation == self.generation
+            && savepoint.len <= self.pending.len()
+            && self
+                .rollbacks
+                .get(savepoint.rollbacks..)
+                .is_some_and(|rollbacks| rollbacks.iter().all(|&len| len >= savepoint.len));
+        assert!(valid, "savepoint is no longer valid");
+        self.truncate_pending(savepoint.len);
+        self
+    }
+
+    /// Drop every operation appended since the last publish, without applying them.
+    ///
+    /// This invalidates every savepoint taken after the first pending operation.
+    pub fn discard_pending(&mut self) -> &mut Self {
+        self.truncate_pending(0);
+        self
+    }
+
+    fn truncate_pending(&mut self, len: usize) {
+        for op in self.pending.drain(len..) {
+            op.discard();
+        }
+        self.rollbacks.push(len);
+    }
+
+    /// Build a batch of operations with `f`, validating it against the map as it goes, and
+    /// publish it only if `f` returns `Ok`.
+    ///
+    /// The [`Transaction`] passed to `f` reads the map as it will be once every pending
+    /// operation is applied. If `f` returns `Err`, every operation it appended is discarded
+    /// and nothing is published. Operations appended before the transaction began are
+    /// published along with it.
+    ///
+    /// Reads through the transaction do not account for evictions, and the keys of any values
+    /// evicted when it is published can be taken with [`take_evicted`](Self::take_evicted).
+    pub fn transaction<R, E, F>(&mut self, f: F) -> Result<R, E>
+    where
+        Op: Clone,
+        F: FnOnce(&mut Transaction<'_, Key, MutV, RefV, Meta, Op, MetaOp>) -> Result<R, E>,
+    {
+        let savepoint = self.savepoint();
+        // The view must be dropped before publishing, otherwise we would wait on ourselves
+        let result = f(&mut Transaction::new(self.read.enter(), &mut self.pending));
+
+        match result {
+            Ok(_) => self.publish_pending(),
+            Err(_) => {
+                self.rollback_to(savepoint);
+            }
+        }
+        result
+    }
+
     pub fn set_meta(&mut self, meta: Meta) {
         self.append_op(Operation::SetMeta(meta));
     }
 
+    /// Update the meta in place, rather than replacing it with a new copy as
+    /// [`set_meta`](Self::set_meta) does.
+    pub fn mutate_meta(&mut self, op: MetaOp) -> &mut Self {
+        self.append_op(Operation::MutateMeta(op))
+    }
+
     pub fn insert(&mut self, k: Key, ref_v: RefV, mut_v: MutV) -> &mut Self {
-        let value = Value {
-            mut_v,
-            ref_v: Aliased::from(ref_v),
-        };
+        let value = Value::new(ref_v, mut_v, None);
+
+        self.append_op(Operation::Insert(k, value))
+    }
+
+    /// Insert a value which readers treat as absent once `ttl` has elapsed.
+    ///
+    /// Expired values still occupy the map until they are removed by [`expire`](Self::expire).
+    /// A `ttl` too long to represent as an [`Instant`], such as [`Duration::MAX`], never
+    /// expires.
+    pub fn insert_with_ttl(
+        &mut self,
+        k: Key,
+        ref_v: RefV,
+        mut_v: MutV,
+        ttl: Duration,
+    ) -> &mut Self {
+        let value = Value::new(ref_v, mut_v, Instant::now().checked_add(ttl));
 
         self.append_op(Operation::Insert(k, value))
     }
 
+    /// Remove every value whose time-to-live had run out by `now`.
+    ///
+    /// `now` is carried in the operation so that both copies of the map remove exactly the
+    /// same values, however long it takes for the operation to be applied.
+    pub fn expire(&mut self, now: Instant) -> &mut Self {
+        self.append_op(Operation::Expire(now))
+    }
+
     pub fn mutate(&mut self, k: Key, op: Op) -> &mut Self {
         self.append_op(Operation::Mutate(k, op))
     }
 
+    /// Apply `op` to the value at `k`, but only if its mutable state equals `expected` at the
+    /// point the operation is applied.
+    ///
+    /// The returned [`Outcome`] resolves to whether `op` was applied once the map is published.
+    /// If the operation is rolled back or discarded before then, it never resolves.
+    pub fn compare_and_mutate(&mut self, k: Key, expected: MutV, op: Op) -> Outcome<bool>
+    where
+        MutV: PartialEq,
+    {
+        let expected = Expected {
+            mut_v: Some(expected),
+            eq: MutV::eq,
+            matched: None,
+        };
+        let outcome = Outcome::new();
+
+        self.append_op(Operation::CompareAndMutate(
+            k,
+            expected,
+            op,
+            outcome.clone(),
+        ));
+        outcome
+    }
+
+    /// Insert a value at `k`, but only if the mutable state at `k` equals `expected` at the
+    /// point the operation is applied. An `expected` of `None` requires `k` to be absent.
+    ///
+    /// The returned [`Outcome`] resolves to whether the value was inserted once the map is
+    /// published. If the operation is rolled back or discarded before then, it never resolves.
+    pub fn compare_and_insert(
+        &mut self,
+        k: Key,
+        expected: Option<MutV>,
+        ref_v: RefV,
+        mut_v: MutV,
+    ) -> Outcome<bool>
+    where
+        MutV: PartialEq,
+    {
+        let expected = Expected {
+            mut_v: expected,
+            eq: MutV::eq,
+            matched: None,
+        };
+        let value = Value::new(ref_v, mut_v, None);
+        let outcome = Outcome::new();
+
+        self.append_op(Operation::CompareAndInsert(
+            k,
+            expected,
+            value,
+            outcome.clone(),
+        ));
+        outcome
+    }
+
     pub fn remove(&mut self, k: Key) -> &mut Self {
         self.append_op(Operation::Remove(k))
     }
 
+    /// Remove the value at `k`, handing ownership of both its parts back rather than dropping
+    /// them, to archive the value or reuse its allocations.
+    ///
+    /// The value can only be handed back once no reader can see it any more, so the returned
+    /// [`Outcome`] resolves when the removal has been applied to both copies of the map. That is
+    /// at the publish after the one which made the removal visible, or when this handle is
+    /// dropped. It resolves to `None` if there was no value at `k`. Use [`Outcome::take`] to get
+    /// the value out.
+    pub fn remove_and_take(&mut self, k: Key) -> Outcome<Option<(RefV, MutV)>> {
+        let outcome = Outcome::new();
+        self.append_op(Operation::RemoveAndTake(k, outcome.clone()));
+        outcome
+    }
+
+    /// Move the value at `old`, both its immutable and mutable parts, to `new` without
+    /// reallocating it. Any value already at `new` is replaced, as if by an insert.
+    ///
+    /// Does nothing if there is no value at `old` when the operation is applied. For eviction the
+    /// move counts as a write of the value at `new`.
+    pub fn rename(&mut self, old: Key, new: Key) -> &mut Self {
+        self.append_op(Operation::Rename(old, new))
+    }
+
+    /// Exchange the values at `k1` and `k2`, both their immutable and mutable parts, in a single
+    /// operation, so no reader ever sees one key updated without the other.
+    ///
+    /// If only one of the keys has a value when the operation is applied, the value is moved to
+    /// the other key as by [`rename`](Self::rename). If neither has one, or the keys are equal,
+    /// nothing changes. For eviction both values count as written.
+    pub fn swap(&mut self, k1: Key, k2: Key) -> &mut Self {
+        self.append_op(Operation::Swap(k1, k2))
+    }
+
     pub fn clear(&mut self) -> &mut Self {
         self.append_op(Operation::Clear)
     }
 }
 
-impl<Key, MutV, RefV, Meta, Op> Extend<(Key, (RefV, MutV))>
-    for WriteHandle<Key, MutV, RefV, Meta, Op>
+impl<Key, MutV, RefV, Meta, Op, MetaOp> Drop for WriteHandle<Key, MutV, RefV, Meta, Op, MetaOp>
+where
+    Key: Eq + Hash + Clone,
+    MutV: Mutable<Op> + Clone,
+    Meta: Mutable<MetaOp> + Clone,
+{
+    fn drop(&mut self) {
+        // Hand pending operations to the oplog so that, as in left_right, they are applied when
+        // the map is torn down rather than leaked.
+        self.write.extend(self.pending.drain(..));
+    }
+}
+
+impl<Key, MutV, RefV, Meta, Op, MetaOp> Extend<(Key, (RefV, MutV))>
+    for WriteHandle<Key, MutV, RefV, Meta, Op, MetaOp>
 where
     Key: Eq + Hash + Clone,
     MutV: Mutable<Op> + Clone,
-    Meta: Clone,
+    Meta: Mutable<MetaOp> + Clone,
 {
     fn extend<T: IntoIterator<Item = (Key, (RefV, MutV))>>(&mut self, iter: T) {
         for (k, v) in iter {
@@ -90,11 +358,11 @@ where
 }
 
 // Allow using the write handle as a read handle
-impl<Key, MutV, RefV, Meta, Op> Deref for WriteHandle<Key, MutV, RefV, Meta, Op>
+impl<Key, MutV, RefV, Meta, Op, MetaOp> Deref for WriteHandle<Key, MutV, RefV, Meta, Op, MetaOp>
 where
     Key: Eq + Hash + Clone,
     MutV: Mutable<Op> + Clone,
-    Meta: Clone,
+    Meta: Mutable<MetaOp> + Clone,
 {
     type Target = ReadHandle<Key, MutV, RefV, Meta>;
 
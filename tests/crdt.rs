@@ -0,0 +1,143 @@
+use sevmap::crdt::{
+    GCounter, GCounterOp, LwwRegister, LwwRegisterOp, Merge, OrSet, OrSetOp, PNCounter, PNCounterOp,
+};
+
+/// Merge `a` and `b` in both orders, checking that they converge
+fn converge<T: Merge + Clone + PartialEq + std::fmt::Debug>(a: &T, b: &T) -> T {
+    let mut ab = a.clone();
+    ab.merge(b);
+    let mut ba = b.clone();
+    ba.merge(a);
+    assert_eq!(ab, ba);
+
+    // Merging again changes nothing
+    let mut again = ab.clone();
+    again.merge(a);
+    again.merge(b);
+    assert_eq!(again, ab);
+    ab
+}
+
+#[test]
+fn counters() {
+    let mut a = GCounter::new();
+    let mut b = GCounter::new();
+    a.increment('a', 2);
+    b.increment('b', 3);
+    b.increment('a', 1);
+    assert_eq!(converge(&a, &b).value(), 5);
+
+    let mut a = PNCounter::new();
+    let mut b = PNCounter::new();
+    a.increment('a', 5);
+    b.decrement('b', 7);
+    assert_eq!(converge(&a, &b).value(), -2);
+}
+
+#[test]
+fn default_needs_no_default_replica() {
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+    struct Replica(u8);
+
+    assert_eq!(GCounter::<Replica>::default().value(), 0);
+    assert_eq!(PNCounter::<Replica>::default().value(), 0);
+    assert!(OrSet::<u8, Replica>::default().is_empty());
+}
+
+#[test]
+fn counters_saturate() {
+    let mut a = GCounter::new();
+    a.increment('a', u64::MAX - 1);
+    a.increment('a', 5);
+    a.increment('b', 1);
+    assert_eq!(a.value(), u64::MAX);
+
+    // A saturated count still wins a merge
+    let mut b = GCounter::new();
+    b.increment('a', 3);
+    assert_eq!(converge(&a, &b).value(), u64::MAX);
+
+    let mut a = PNCounter::new();
+    a.increment('a', u64::MAX);
+    assert_eq!(a.value(), i64::MAX);
+    a.decrement('b', u64::MAX);
+    assert_eq!(a.value(), 0);
+
+    let mut b = PNCounter::new();
+    b.decrement('b', u64::MAX);
+    assert_eq!(b.value(), i64::MIN);
+}
+
+#[test]
+fn lww_register() {
+    let mut a = LwwRegister::new("initial", 0, 'a');
+    let mut b = a.clone();
+    a.set("from a", 2, 'a');
+    b.set("from b", 2, 'b');
+    b.set("stale", 1, 'b');
+    assert_eq!(converge(&a, &b).value(), &"from b");
+}
+
+#[test]
+fn or_set() {
+    let mut a = OrSet::new();
+    a.add("x", 'a');
+    let mut b = a.clone();
+
+    // A concurrent add wins over a remove which did not observe it
+    a.remove(&"x");
+    b.add("x", 'b');
+    b.add("y", 'b');
+    let merged = converge(&a, &b);
+    assert_eq!(merged.iter().collect::<Vec<_>>(), [&"x", &"y"]);
+
+    // A remove which observed every add removes the element
+    let mut c = merged.clone();
+    c.remove(&"x");
+    assert!(!converge(&merged, &c).contains(&"x"));
+}
+
+#[test]
+fn merge_into_map() {
+    let (mut w, r) = sevmap::new::<&str, PNCounter<u8>, (), PNCounterOp<u8>>();
+    w.insert("hits", (), PNCounter::new());
+    w.mutate("hits", PNCounterOp::Increment(0, 3));
+    w.publish();
+
+    // Fold in the state of a remote replica
+    let mut remote = PNCounter::new();
+    remote.increment(1, 10);
+    remote.decrement(1, 4);
+    w.mutate("hits", PNCounterOp::Merge(remote.clone()));
+    w.mutate("hits", PNCounterOp::Merge(remote));
+    w.publish();
+    assert_eq!(r.get(&"hits").unwrap().mut_v().value(), 9);
+    w.publish();
+    assert_eq!(r.get(&"hits").unwrap().mut_v().value(), 9);
+
+    let (mut w, r) = sevmap::new::<u8, OrSet<&str, u8>, (), OrSetOp<&str, u8>>();
+    w.insert(0, (), OrSet::new());
+    w.mutate(0, OrSetOp::Add("x", 0));
+    w.mutate(0, OrSetOp::Add("y", 0));
+    w.mutate(0, OrSetOp::Remove("x"));
+    w.publish();
+    w.publish();
+    assert_eq!(
+        r.get(&0).unwrap().mut_v().iter().collect::<Vec<_>>(),
+        [&"y"]
+    );
+
+    let (mut w, r) = sevmap::new::<u8, GCounter<u8>, (), GCounterOp<u8>>();
+    w.insert(0, (), GCounter::new());
+    w.mutate(0, GCounterOp::Increment(0, 1));
+    w.publish();
+    assert_eq!(r.get(&0).unwrap().mut_v().value(), 1);
+
+    let (mut w, r) = sevmap::new::<u8, LwwRegister<u8, u8>, (), LwwRegisterOp<u8, u8>>();
+    w.insert(0, (), LwwRegister::new(0, 0, 0));
+    w.mutate(0, LwwRegisterOp::Set(1, 1, 0));
+    w.mutate(0, LwwRegisterOp::Merge(LwwRegister::new(2, 1, 1)));
+    w.publish();
+    w.publish();
+    assert_eq!(r.get(&0).unwrap().mut_v().value(), &2);
+}
@@ -0,0 +1,98 @@
+#[test]
+fn immutable() {
+    let (mut w, r) = sevmap::immutable::new::<u32, String>();
+    assert!(r.get(&1).is_none());
+
+    w.insert(1, "one".to_string());
+    w.extend([(2, "two".to_string()), (3, "three".to_string())]);
+    w.publish();
+
+    assert_eq!(r.get(&1).as_deref().map(String::as_str), Some("one"));
+    assert_eq!(r.get_and(&2, |v| v.len()), Some(3));
+    assert_eq!(r.get_cloned(&3), Some("three".to_string()));
+    assert_eq!(w.len(), 3);
+
+    w.remove(1);
+    w.publish();
+    assert!(!r.contains_key(&1));
+
+    let removed = w.remove_and_take(3);
+    w.publish();
+    w.publish();
+    assert_eq!(removed.take(), Some(Some("three".to_string())));
+    w.insert(3, "three".to_string());
+    w.publish();
+
+    let map = r.enter().unwrap();
+    assert_eq!(map.get(&2).map(String::as_str), Some("two"));
+    let mut values: Vec<_> = map.values().cloned().collect();
+    values.sort();
+    assert_eq!(values, ["three", "two"]);
+    let mut keys: Vec<_> = map.iter().map(|(k, _)| *k).collect();
+    keys.sort();
+    assert_eq!(keys, [2, 3]);
+}
+
+#[test]
+fn mutable_only() {
+    use sevmap::muts::Counter;
+
+    let (mut w, r) = sevmap::mutable_only::new::<char, u32, Counter<u32>>();
+    w.insert('a', 1);
+    w.extend([('b', 10)]);
+    w.mutate('a', Counter::Add(4));
+    w.publish();
+
+    assert_eq!(r.get(&'a').as_deref(), Some(&5));
+    assert_eq!(r.get_cloned(&'b'), Some(10));
+    assert_eq!(r.get_and(&'b', |v| v * 2), Some(20));
+
+    let applied = w.compare_and_mutate('b', 11, Counter::Set(0));
+    let inserted = w.compare_and_insert('c', None, 3);
+    let replaced = w.compare_and_insert('c', Some(4), 5);
+    w.remove('a');
+    w.publish();
+    assert_eq!(applied.get(), Some(false));
+    assert_eq!(inserted.get(), Some(true));
+    assert_eq!(replaced.get(), Some(false));
+    let removed = w.remove_and_take('c');
+    w.publish();
+    assert!(!removed.is_resolved());
+    w.publish();
+    assert_eq!(removed.take(), Some(Some(3)));
+    assert!(!r.contains_key(&'a'));
+
+    let map = r.enter().unwrap();
+    assert_eq!(map.get(&'b'), Some(&10));
+    assert_eq!(map.iter().collect::<Vec<_>>(), [(&'b', &10)]);
+    assert_eq!(map.values().sum::<u32>(), 10);
+}
+
+#[test]
+fn mutable_only_drops_each_copy() {
+    use std::sync::Arc;
+
+    let value = Arc::new(());
+    let (mut w, r) = sevmap::mutable_only::new::<char, Arc<()>, ()>();
+    w.insert('a', value.clone());
+    w.publish();
+    w.publish();
+    // Each copy of the map holds its own clone
+    assert_eq!(Arc::strong_count(&value), 3);
+
+    w.insert('a', value.clone());
+    w.publish();
+    w.publish();
+    assert_eq!(Arc::strong_count(&value), 3);
+
+    w.remove('a');
+    w.publish();
+    w.publish();
+    assert!(!r.contains_key(&'a'));
+    assert_eq!(Arc::strong_count(&value), 1);
+
+    w.insert('b', value.clone());
+    drop(w);
+    drop(r);
+    assert_eq!(Arc::strong_count(&value), 1);
+}
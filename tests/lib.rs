@@ -1,3 +1,9 @@
 extern crate sevmap;
 
+mod crdt;
+mod facades;
+mod ops;
+mod read;
+mod sharded;
 mod single;
+mod write;
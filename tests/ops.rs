@@ -0,0 +1,236 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+
+use sevmap::muts::{Counter, MapOp, Mutable, OptionOp, SetOp, VecOp};
+
+/// Apply `ops` to `value` as both copies of the map would, checking that they agree
+pub(crate) fn apply<T, O>(value: T, ops: Vec<O>) -> T
+where
+    T: Mutable<O> + PartialEq + Debug,
+    O: Clone,
+{
+    let mut first = value.clone();
+    let mut second = value;
+    for op in ops {
+        first.mutate_first(&mut op.clone());
+        second.mutate_second(op);
+    }
+    assert_eq!(first, second);
+    first
+}
+
+#[test]
+fn counter() {
+    use Counter::*;
+
+    assert_eq!(apply(10i64, vec![Add(5), Sub(3), Max(20), Min(15)]), 15);
+    assert_eq!(apply(250u8, vec![Add(10)]), 4);
+    assert_eq!(apply(250u8, vec![SaturatingAdd(10)]), 255);
+    assert_eq!(apply(5u8, vec![SaturatingSub(10), Add(1)]), 1);
+    assert_eq!(apply(-1i32, vec![Set(7)]), 7);
+}
+
+#[test]
+fn vec_op() {
+    use VecOp::*;
+
+    let ops = vec![
+        Push(1),
+        Push(2),
+        Push(3),
+        SetAt(0, 10),
+        SetAt(9, 0),
+        Pop,
+        Push(4),
+    ];
+    assert_eq!(apply(vec![], ops), vec![10, 2, 4]);
+    assert_eq!(apply(vec![1, 2, 3], vec![Truncate(1)]), vec![1]);
+    assert_eq!(apply(vec![1], vec![Pop, Pop, Clear]), Vec::<i32>::new());
+}
+
+#[test]
+fn set_and_map_op() {
+    let set = apply(
+        BTreeSet::new(),
+        vec![
+            SetOp::Insert(3),
+            SetOp::Insert(1),
+            SetOp::Remove(3),
+            SetOp::Insert(2),
+        ],
+    );
+    assert_eq!(set, BTreeSet::from([1, 2]));
+    assert!(apply(set, vec![SetOp::Clear]).is_empty());
+
+    let map = apply(
+        BTreeMap::new(),
+        vec![
+            MapOp::Insert('a', 1),
+            MapOp::Insert('b', 2),
+            MapOp::Insert('a', 3),
+            MapOp::Remove('b'),
+        ],
+    );
+    assert_eq!(map, BTreeMap::from([('a', 3)]));
+    assert!(apply(map, vec![MapOp::Clear]).is_empty());
+}
+
+#[test]
+fn option_op() {
+    use OptionOp::*;
+
+    assert_eq!(
+        apply(None, vec![Mutate(Counter::Add(1)), GetOrInsert(5)]),
+        Some(5)
+    );
+    assert_eq!(
+        apply(Some(1), vec![GetOrInsert(5), Mutate(Counter::Add(1))]),
+        Some(2)
+    );
+    assert_eq!(apply(Some(1), vec![Set::<_, ()>(3), Clear]), None::<i32>);
+}
+
+#[test]
+fn ops_in_map() {
+    let (mut w, r) = sevmap::new::<char, Vec<String>, (), VecOp<String>>();
+    w.insert('a', (), vec![]);
+    w.mutate('a', VecOp::Push("x".to_string()));
+    w.mutate('a', VecOp::Push("y".to_string()));
+    w.publish();
+    w.mutate('a', VecOp::SetAt(0, "z".to_string()));
+    w.publish();
+
+    assert_eq!(r.get(&'a').unwrap().mut_v(), &["z", "y"]);
+    // Publish again so that the other copy is read too
+    w.publish();
+    assert_eq!(r.get(&'a').unwrap().mut_v(), &["z", "y"]);
+}
+
+#[test]
+fn combinators() {
+    use sevmap::muts::{Either, Field, Seq};
+
+    let ops = vec![
+        Either::Left(Counter::Add(2)),
+        Either::Right(VecOp::Push('x')),
+        Either::Left(Counter::Sub(1)),
+    ];
+    assert_eq!(apply((0u32, vec![]), ops), (1, vec!['x']));
+
+    let ops = vec![Seq(vec![Counter::Add(1), Counter::Max(10)]), Seq(vec![])];
+    assert_eq!(apply(0i64, ops), 10);
+
+    // Nest combinators to build operations on the parts of a compound value
+    let op = Either::<(), _>::Right(Seq(vec![
+        OptionOp::Set(1u8),
+        OptionOp::Mutate(Counter::Add(1)),
+    ]));
+    assert_eq!(apply(((), None), vec![op]), ((), Some(2)));
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Stats {
+        hits: u64,
+        misses: u64,
+    }
+    type StatsOp = Field<fn(&mut Stats) -> &mut u64, Counter<u64>>;
+
+    let hits: fn(&mut Stats) -> &mut u64 = |stats| &mut stats.hits;
+    let misses: fn(&mut Stats) -> &mut u64 = |stats| &mut stats.misses;
+
+    let (mut w, r) = sevmap::new::<char, Stats, (), Seq<StatsOp>>();
+    w.insert('a', (), Stats { hits: 0, misses: 0 });
+    w.mutate(
+        'a',
+        Seq(vec![
+            Field::new(hits, Counter::Add(3)),
+            Field::new(misses, Counter::Add(1)),
+            Field::new(hits, Counter::Sub(1)),
+        ]),
+    );
+    w.publish();
+    w.publish();
+
+    assert_eq!(r.get_mut_v_cloned(&'a'), Some(Stats { hits: 2, misses: 1 }));
+}
+
+#[test]
+fn moving_ops_clone_once() {
+    use sevmap::muts::{Moving, OwnedOp};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, PartialEq)]
+    struct Payload(Vec<u8>);
+
+    impl Clone for Payload {
+        fn clone(&self) -> Self {
+            CLONES.fetch_add(1, Ordering::SeqCst);
+            Payload(self.0.clone())
+        }
+    }
+
+    #[derive(Clone)]
+    struct Replace(Payload);
+
+    impl OwnedOp<Option<Payload>> for Replace {
+        fn apply(self, target: &mut Option<Payload>) {
+            *target = Some(self.0);
+        }
+    }
+
+    let (mut w, r) = sevmap::new::<char, Option<Payload>, (), Moving<Replace>>();
+    w.insert('a', (), None);
+    w.publish();
+    CLONES.store(0, Ordering::SeqCst);
+
+    w.mutate('a', Moving(Replace(Payload(vec![1, 2, 3]))));
+    w.publish();
+    w.publish();
+    assert_eq!(CLONES.load(Ordering::SeqCst), 1);
+    assert_eq!(r.get(&'a').unwrap().mut_v(), &Some(Payload(vec![1, 2, 3])));
+
+    assert_eq!(
+        apply(vec![1], vec![VecOp::Append(vec![2, 3]), VecOp::Push(4)]),
+        vec![1, 2, 3, 4]
+    );
+}
+
+#[test]
+fn fn_op() {
+    use sevmap::muts::FnOp;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Stats {
+        count: u64,
+        last: Option<&'static str>,
+    }
+
+    let (mut w, r) = sevmap::new::<char, Stats, (), FnOp<Stats>>();
+    w.insert(
+        'a',
+        (),
+        Stats {
+            count: 0,
+            last: None,
+        },
+    );
+    for name in ["x", "y"] {
+        w.mutate(
+            'a',
+            FnOp::new(move |v: &mut Stats| {
+                v.count += 1;
+                v.last = Some(name);
+            }),
+        );
+    }
+    w.publish();
+    w.publish();
+    assert_eq!(
+        r.get_mut_v_cloned(&'a'),
+        Some(Stats {
+            count: 2,
+            last: Some("y")
+        })
+    );
+}
@@ -0,0 +1,223 @@
+use std::collections::{BTreeMap, HashMap};
+
+#[test]
+fn snapshot() {
+    let (mut w, r) = sevmap::new::<char, i32, &str, ()>();
+    assert!(r.snapshot().is_empty());
+
+    w.insert('a', "a", 1);
+    w.insert('b', "b", 2);
+    w.publish();
+
+    let snapshot = r.snapshot();
+    assert_eq!(snapshot, HashMap::from([('a', ("a", 1)), ('b', ("b", 2))]));
+
+    // The snapshot is owned, so it outlives later publishes
+    w.remove('a');
+    w.publish();
+    assert_eq!(snapshot.len(), 2);
+
+    let mut sorted = BTreeMap::new();
+    r.enter().unwrap().collect_into(&mut sorted);
+    assert_eq!(sorted, BTreeMap::from([('b', ("b", 2))]));
+}
+
+#[test]
+fn owned_lookups() {
+    let (mut w, r) = sevmap::new::<char, i32, String, ()>();
+    assert_eq!(r.get_mut_v_cloned(&'a'), None);
+
+    w.insert('a', "a".to_string(), 1);
+    w.publish();
+
+    assert_eq!(r.get_and(&'a', |value| value.ref_v().len()), Some(1));
+    assert_eq!(r.get_and(&'b', |value| value.ref_v().len()), None);
+    assert_eq!(r.get_cloned(&'a'), Some(("a".to_string(), 1)));
+    assert_eq!(r.get_mut_v_cloned(&'a'), Some(1));
+
+    // No guard is held across the publish
+    w.remove('a');
+    w.publish();
+    assert_eq!(r.get_cloned(&'a'), None);
+}
+
+#[test]
+fn get_many() {
+    let (mut w, r) = sevmap::new::<char, i32, &str, ()>();
+    assert_eq!(r.get_many(&['a', 'b']), vec![None, None]);
+
+    w.insert('a', "a", 1);
+    w.insert('c', "c", 3);
+    w.publish();
+
+    assert_eq!(
+        r.get_many(&['a', 'b', 'c']),
+        vec![Some(("a", 1)), None, Some(("c", 3))]
+    );
+    assert_eq!(
+        r.get_many_and(&['c', 'a'], |value| *value.mut_v()),
+        vec![Some(3), Some(1)]
+    );
+
+    let map = r.enter().unwrap();
+    let values: Vec<_> = map
+        .get_many(&['a', 'b'])
+        .into_iter()
+        .map(|value| value.map(|value| *value.ref_v()))
+        .collect();
+    assert_eq!(values, vec![Some("a"), None]);
+}
+
+#[test]
+fn scan() {
+    use sevmap::handles::Cursor;
+
+    let (mut w, r) = sevmap::new::<u32, u32, (), ()>();
+    for k in 0..10 {
+        w.insert(k * 2, (), k);
+    }
+    w.publish();
+
+    let mut cursor = Cursor::new();
+    let keys = |page: Vec<(u32, ((), u32))>| page.into_iter().map(|(k, _)| k).collect::<Vec<_>>();
+    assert_eq!(keys(r.scan(&mut cursor, 4)), vec![0, 2, 4, 6]);
+    assert_eq!(cursor.last_key(), Some(&6));
+
+    // Changes behind the cursor are not seen, changes ahead of it are
+    w.remove(8);
+    w.insert(1, (), 0);
+    w.insert(9, (), 0);
+    w.publish();
+
+    assert_eq!(keys(r.scan(&mut cursor, 4)), vec![9, 10, 12, 14]);
+    assert!(!cursor.is_finished());
+    assert_eq!(keys(r.scan(&mut cursor, 4)), vec![16, 18]);
+    assert!(cursor.is_finished());
+    assert!(r.scan(&mut cursor, 4).is_empty());
+
+    // A finished scan still picks up keys inserted after the cursor
+    w.insert(17, (), 0);
+    w.insert(20, (), 0);
+    w.publish();
+    assert_eq!(keys(r.scan(&mut cursor, 4)), vec![20]);
+    assert!(cursor.is_finished());
+}
+
+#[test]
+fn scan_ordered_keys() {
+    use sevmap::handles::Cursor;
+    use std::time::Duration;
+
+    let (mut w, r) = sevmap::Options::default()
+        .for_map::<u32, u32, ()>()
+        .with_ordered_keys()
+        .construct::<()>();
+
+    // Nothing is published yet, so the cursor stays where it is
+    let mut cursor = Cursor::new();
+    assert!(r.scan(&mut cursor, 4).is_empty());
+    assert!(!cursor.is_finished());
+
+    for k in 0..10 {
+        w.insert(k * 2, (), k);
+    }
+    w.insert_with_ttl(3, (), 0, Duration::ZERO);
+    w.publish();
+
+    let keys = |page: Vec<(u32, ((), u32))>| page.into_iter().map(|(k, _)| k).collect::<Vec<_>>();
+    assert_eq!(keys(r.scan(&mut cursor, 4)), vec![0, 2, 4, 6]);
+
+    w.remove(8);
+    w.rename(2, 11);
+    w.swap(12, 13);
+    w.publish();
+
+    assert_eq!(keys(r.scan(&mut cursor, 4)), vec![10, 11, 13, 14]);
+    assert!(!cursor.is_finished());
+    assert_eq!(keys(r.scan(&mut cursor, 4)), vec![16, 18]);
+    assert!(cursor.is_finished());
+
+    w.insert(20, (), 0);
+    w.publish();
+    assert_eq!(keys(r.scan(&mut cursor, 4)), vec![20]);
+    assert!(cursor.is_finished());
+
+    // Both copies keep their keys in order
+    w.clear();
+    w.insert(5, (), 5);
+    w.publish();
+    w.publish();
+    let mut cursor = Cursor::new();
+    assert_eq!(keys(r.scan(&mut cursor, 4)), vec![5]);
+    assert!(cursor.is_finished());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_iter() {
+    use rayon::iter::ParallelIterator;
+
+    let (mut w, r) = sevmap::new::<u64, u64, u64, ()>();
+    for k in 0..1000 {
+        w.insert(k, k * 2, k);
+    }
+    w.publish();
+
+    let map = r.enter().unwrap();
+    assert_eq!(map.par_keys().sum::<u64>(), 499500);
+    assert_eq!(map.par_values().map(|v| *v.ref_v()).sum::<u64>(), 999000);
+    assert_eq!(
+        map.par_iter().filter(|(k, v)| *k == v.mut_v()).count(),
+        1000
+    );
+}
+
+#[test]
+fn secondary_index() {
+    let (mut w, r) = sevmap::Options::default()
+        .for_map()
+        .with_max_entries(3, sevmap::EvictionPolicy::Fifo)
+        .with_index("owner", |_: &u32, ref_v: &(&str, u32)| ref_v.0)
+        .construct::<()>();
+
+    let owned_by = |owner: &'static str| {
+        let map = r.enter().unwrap();
+        let mut keys: Vec<u32> = map.by_index("owner", &owner).map(|(k, _)| *k).collect();
+        keys.sort();
+        keys
+    };
+
+    w.insert(1, ("alice", 10), 0);
+    w.insert(2, ("bob", 20), 0);
+    w.insert(3, ("alice", 30), 0);
+    w.publish();
+    assert_eq!(owned_by("alice"), vec![1, 3]);
+    assert_eq!(owned_by("bob"), vec![2]);
+    assert_eq!(owned_by("carol"), Vec::<u32>::new());
+
+    // Replacing, removing and evicting values all move them out of the index
+    w.insert(3, ("bob", 30), 0);
+    w.remove(2);
+    w.insert(4, ("carol", 40), 0);
+    w.insert(5, ("carol", 50), 0);
+    w.publish();
+    assert_eq!(owned_by("alice"), Vec::<u32>::new());
+    assert_eq!(owned_by("bob"), vec![3]);
+    assert_eq!(owned_by("carol"), vec![4, 5]);
+
+    // Both copies are kept in step
+    w.clear();
+    w.insert(6, ("alice", 60), 0);
+    w.publish();
+    w.publish();
+    assert_eq!(owned_by("alice"), vec![6]);
+    assert_eq!(owned_by("carol"), Vec::<u32>::new());
+}
+
+#[test]
+#[should_panic(expected = "no index named")]
+fn unknown_index() {
+    let (mut w, r) = sevmap::new::<u32, (), (), ()>();
+    w.publish();
+    r.enter().unwrap().by_index("owner", &1).count();
+}
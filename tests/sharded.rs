@@ -0,0 +1,70 @@
+#[test]
+fn sharded_writers() {
+    let (w, r) = sevmap::sharded::new::<u32, u32, (), ()>(4);
+    assert_eq!(w.shards(), 4);
+    assert!(r.is_empty());
+
+    std::thread::scope(|scope| {
+        for t in 0..4 {
+            let w = &w;
+            scope.spawn(move || {
+                for k in (t * 100)..((t + 1) * 100) {
+                    w.insert(k, (), k);
+                }
+            });
+        }
+    });
+    w.publish();
+
+    assert_eq!(r.len(), 400);
+    assert_eq!(*r.get(&123).unwrap().mut_v(), 123);
+    assert_eq!(r.get_and(&399, |v| *v.mut_v()), Some(399));
+    assert!(!r.contains_key(&400));
+    assert_eq!(r.shard(r.shard_of(&7)).get_and(&7, |v| *v.mut_v()), Some(7));
+
+    let total: u32 = r
+        .enter_all()
+        .iter()
+        .flat_map(|shard| shard.values().map(|v| *v.mut_v()))
+        .sum();
+    assert_eq!(total, (0..400).sum());
+    assert_eq!(r.snapshot().len(), 400);
+
+    // One handle per shard
+    let (mut shards, r) = w.into_shards();
+    let shard = r.shard_of(&7);
+    shards[shard].remove(7);
+    shards[shard].publish();
+    assert!(!r.contains_key(&7));
+    assert_eq!(r.len(), 399);
+}
+
+#[test]
+fn sharded_forwarding() {
+    use std::time::{Duration, Instant};
+
+    let (w, r) = sevmap::sharded::new::<u32, u32, (), ()>(4);
+    for k in 0..20 {
+        w.insert(k, (), k);
+    }
+    w.insert_with_ttl(20, (), 20, Duration::ZERO);
+    let inserted = w.compare_and_insert(21, None, (), 21);
+    assert!(w.has_pending());
+    w.publish();
+    assert!(!w.has_pending());
+    assert_eq!(inserted.get(), Some(true));
+    assert_eq!(r.len(), 21);
+
+    let taken = w.remove_and_take(3);
+    w.expire(Instant::now());
+    w.publish();
+    w.publish();
+    assert_eq!(taken.take(), Some(Some(((), 3))));
+    assert!(!r.contains_key(&3));
+
+    w.insert(30, (), 30);
+    w.discard_pending();
+    w.clear();
+    w.publish();
+    assert!(r.is_empty());
+}
@@ -100,3 +100,169 @@ fn single_works() {
     assert_match!(r.get(&x.0), None);
     assert_eq!(*r.meta().unwrap(), 1502);
 }
+
+#[test]
+fn compare_and_set() {
+    let (mut w, r) = sevmap::new();
+
+    let inserted = w.compare_and_insert('x', None, "x", 10);
+    let duplicate = w.compare_and_insert('x', None, "y", 20);
+    assert_eq!(inserted.get(), None);
+
+    w.publish();
+    assert_eq!(inserted.get(), Some(true));
+    assert_eq!(duplicate.get(), Some(false));
+    assert_eq!(r.get(&'x').unwrap().ref_v(), &"x");
+
+    // Only the first of two racing updates from the same starting point applies
+    let first = w.compare_and_mutate('x', 10, MutateValue::Increment(1));
+    let second = w.compare_and_mutate('x', 10, MutateValue::Increment(5));
+    let missing = w.compare_and_mutate('y', 10, MutateValue::Increment(1));
+    w.publish();
+    assert_eq!(first.get(), Some(true));
+    assert_eq!(second.get(), Some(false));
+    assert_eq!(missing.get(), Some(false));
+    assert_eq!(r.get(&'x').unwrap().mut_v(), &11);
+
+    // Both copies must agree on the outcome
+    w.compare_and_insert('x', Some(11), "z", 0);
+    w.publish();
+    w.publish();
+    assert_eq!(r.get(&'x').unwrap().ref_v(), &"z");
+    assert_eq!(r.get(&'x').unwrap().mut_v(), &0);
+}
+
+#[test]
+fn discarded_outcome_never_resolves() {
+    let (mut w, _r) = sevmap::new::<char, i32, (), MutateValue>();
+
+    let sp = w.savepoint();
+    let rolled_back = w.compare_and_insert('x', None, (), 1);
+    w.rollback_to(sp);
+    let discarded = w.compare_and_mutate('x', 1, MutateValue::Increment(1));
+    w.discard_pending();
+    w.publish();
+    w.publish();
+    assert!(!rolled_back.is_resolved());
+    assert!(!discarded.is_resolved());
+}
+
+#[test]
+fn mutate_meta() {
+    let (mut w, r) = sevmap::Options::default()
+        .with_meta(100)
+        .with_meta_op::<MutateValue>()
+        .construct::<char, i32, (), ()>();
+
+    w.mutate_meta(MutateValue::Increment(5));
+    w.mutate_meta(MutateValue::Decrement(2));
+    w.publish();
+    assert_eq!(*r.meta().unwrap(), 103);
+
+    w.set_meta(1);
+    w.mutate_meta(MutateValue::Increment(1));
+    w.publish();
+    w.publish();
+    assert_eq!(*r.meta().unwrap(), 2);
+}
+
+#[test]
+fn bounded_eviction() {
+    use sevmap::EvictionPolicy;
+
+    // LRU: mutating a value makes it recent again
+    let (mut w, r) = sevmap::Options::default()
+        .for_map()
+        .with_max_entries(2, EvictionPolicy::Lru)
+        .construct();
+    w.insert('a', (), 1);
+    w.insert('b', (), 2);
+    w.mutate('a', MutateValue::Increment(1));
+    w.insert('c', (), 3);
+    w.publish();
+    assert_eq!(w.take_evicted(), vec!['b']);
+    assert!(r.contains_key(&'a') && r.contains_key(&'c'));
+
+    // Both copies must evict the same values
+    w.publish();
+    assert_eq!(r.len(), 2);
+    assert!(!r.contains_key(&'b'));
+
+    // Evictions build up across publishes until they are taken
+    w.insert('d', (), 4);
+    w.publish();
+    w.insert('e', (), 5);
+    w.publish();
+    assert_eq!(w.take_evicted(), vec!['a', 'c']);
+    assert!(w.take_evicted().is_empty());
+
+    // FIFO ignores mutations
+    let (mut w, r) = sevmap::Options::default()
+        .for_map()
+        .with_max_entries(2, EvictionPolicy::Fifo)
+        .construct();
+    w.insert('a', (), 1);
+    w.insert('b', (), 2);
+    w.mutate('a', MutateValue::Increment(1));
+    w.insert('c', (), 3);
+    w.publish();
+    assert_eq!(w.take_evicted(), vec!['a']);
+    assert!(!r.contains_key(&'a'));
+
+    // Lowest mutable state first, oldest first on ties
+    let (mut w, r) = sevmap::Options::default()
+        .for_map()
+        .with_max_entries(2, EvictionPolicy::LowestMutV(i32::cmp))
+        .construct();
+    w.insert('a', (), 5);
+    w.insert('b', (), 1);
+    w.insert('c', (), 1);
+    w.insert('d', (), 9);
+    w.mutate('d', MutateValue::Decrement(0));
+    w.publish();
+    assert_eq!(w.take_evicted(), vec!['b', 'c']);
+    w.publish();
+    assert!(r.contains_key(&'a') && r.contains_key(&'d'));
+}
+
+#[test]
+fn aggregates() {
+    use sevmap::Aggregate;
+
+    let (mut w, r) = sevmap::Options::default()
+        .for_map::<char, i32, u32>()
+        .with_aggregate("total", Aggregate::Sum, |_, _, mut_v| Some(*mut_v as i64))
+        .with_aggregate("weighted", Aggregate::Count, |_, ref_v, _| {
+            (*ref_v > 1).then_some(0)
+        })
+        .with_aggregate("min", Aggregate::Min, |_, _, mut_v| Some(*mut_v as i64))
+        .with_aggregate("max", Aggregate::Max, |_, _, mut_v| Some(*mut_v as i64))
+        .construct::<MutateValue>();
+
+    let aggregates = |r: &sevmap::handles::ReadHandle<char, i32, u32, ()>| {
+        let map = r.enter().unwrap();
+        ["total", "weighted", "min", "max"].map(|name| map.aggregate(name))
+    };
+
+    w.publish();
+    assert_eq!(aggregates(&r), [Some(0), Some(0), None, None]);
+
+    w.insert('a', 1, 10);
+    w.insert('b', 2, 20);
+    w.insert('c', 3, 30);
+    w.publish();
+    assert_eq!(aggregates(&r), [Some(60), Some(2), Some(10), Some(30)]);
+
+    w.mutate('a', MutateValue::Increment(25));
+    w.remove('c');
+    w.insert('b', 1, 5);
+    w.publish();
+    assert_eq!(aggregates(&r), [Some(40), Some(0), Some(5), Some(35)]);
+
+    // Both copies are kept in step
+    w.clear();
+    w.insert('d', 4, -1);
+    w.publish();
+    w.publish();
+    assert_eq!(aggregates(&r), [Some(-1), Some(1), Some(-1), Some(-1)]);
+}
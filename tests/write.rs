@@ -0,0 +1,369 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A `RefV` which counts how many times it has been dropped
+struct DropCounter(Arc<AtomicUsize>);
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn savepoint_rollback() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (mut w, r) = sevmap::new::<u32, i32, DropCounter, ()>();
+
+    w.insert(1, DropCounter(drops.clone()), 1);
+    let sp = w.savepoint();
+    w.insert(2, DropCounter(drops.clone()), 2);
+    w.remove(1);
+    w.rollback_to(sp);
+
+    // The rolled back insert was dropped exactly once, the rolled back remove never happened
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+    assert!(w.has_pending());
+
+    w.publish();
+    assert_eq!(r.get(&1).unwrap().mut_v(), &1);
+    assert!(r.get(&2).is_none());
+
+    w.insert(3, DropCounter(drops.clone()), 3);
+    w.insert(4, DropCounter(drops.clone()), 4);
+    w.discard_pending();
+    assert_eq!(drops.load(Ordering::SeqCst), 3);
+
+    w.publish();
+    assert_eq!(r.len(), 1);
+
+    drop(w);
+    drop(r);
+    assert_eq!(drops.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+#[should_panic(expected = "savepoint is no longer valid")]
+fn savepoint_invalidated_by_publish() {
+    let (mut w, _r) = sevmap::new::<u32, i32, (), ()>();
+
+    let sp = w.savepoint();
+    w.insert(1, (), 1);
+    w.publish();
+    w.rollback_to(sp);
+}
+
+#[test]
+fn transaction_all_or_nothing() {
+    let (mut w, r) = sevmap::new::<char, i32, &str, ()>();
+    w.insert('a', "a", 1);
+    w.publish();
+
+    // Reads see the published map plus everything staged so far
+    let result = w.transaction(|tx| {
+        tx.insert('b', "b", 2);
+        tx.remove('a');
+        if tx.contains_key(&'a') {
+            return Err("a should have been removed");
+        }
+        tx.ref_v(&'b').copied().ok_or("b should be staged")
+    });
+    assert_eq!(result, Ok("b"));
+    assert!(!r.contains_key(&'a'));
+    assert_eq!(r.get(&'b').unwrap().mut_v(), &2);
+
+    // A failed check discards the whole batch
+    w.insert('c', "c", 3);
+    let result: Result<(), _> = w.transaction(|tx| {
+        tx.insert('d', "d", 4);
+        match tx.mut_v(&'c') {
+            Some(&3) => Err("c is staged but not published"),
+            _ => Ok(()),
+        }
+    });
+    assert!(result.is_err());
+    assert!(!r.contains_key(&'c'));
+
+    w.publish();
+    assert!(r.contains_key(&'c'));
+    assert!(!r.contains_key(&'d'));
+}
+
+#[test]
+fn ttl_expiry() {
+    use std::time::{Duration, Instant};
+
+    let (mut w, r) = sevmap::new::<u32, i32, (), ()>();
+    w.insert_with_ttl(1, (), 1, Duration::ZERO);
+    w.insert_with_ttl(2, (), 2, Duration::from_secs(3600));
+    w.insert(3, (), 3);
+    w.insert_with_ttl(4, (), 4, Duration::MAX);
+    w.publish();
+    assert!(r.get(&4).unwrap().deadline().is_none());
+    w.remove(4);
+    w.publish();
+
+    // Expired values are treated as absent, even before they are removed
+    assert!(r.get(&1).is_none());
+    assert!(!r.contains_key(&1));
+    assert!(r.get(&2).unwrap().deadline().is_some());
+    assert_eq!(r.enter().unwrap().iter().count(), 2);
+    assert_eq!(r.len(), 2);
+
+    w.expire(Instant::now());
+    w.publish();
+    assert_eq!(r.len(), 2);
+
+    w.expire(Instant::now() + Duration::from_secs(7200));
+    w.publish();
+    w.publish();
+    assert_eq!(r.len(), 1);
+    assert!(r.contains_key(&3));
+}
+
+#[test]
+fn transaction_expiry_hides_published() {
+    use std::time::{Duration, Instant};
+
+    let (mut w, r) = sevmap::new::<u32, i32, (), ()>();
+    w.insert(1, (), 1);
+    w.publish();
+
+    // The pending insert shadows the published value, then expires, and must not bring the
+    // published value back
+    w.insert_with_ttl(1, (), 2, Duration::ZERO);
+    w.expire(Instant::now());
+    let seen = w.transaction(|tx| Ok::<_, ()>(tx.mut_v(&1).copied()));
+    assert_eq!(seen, Ok(None));
+    w.publish();
+    assert!(r.get(&1).is_none());
+}
+
+#[test]
+fn compare_and_insert_over_expired() {
+    use std::time::Duration;
+
+    let (mut w, r) = sevmap::new::<u32, i32, &str, ()>();
+    w.insert_with_ttl(1, "old", 1, Duration::ZERO);
+    w.publish();
+    assert!(r.is_empty());
+
+    // A reader sees the key as absent, so a writer can fill it as absent too
+    let mutated = w.compare_and_mutate(1, 1, ());
+    let inserted = w.compare_and_insert(1, None, "new", 2);
+    w.publish();
+    w.publish();
+    assert_eq!(mutated.get(), Some(false));
+    assert_eq!(inserted.get(), Some(true));
+    assert_eq!(r.get(&1).unwrap().ref_v(), &"new");
+    assert_eq!(r.len(), 1);
+}
+
+#[test]
+fn access_tracking() {
+    use sevmap::EvictionPolicy;
+
+    let (mut w, r) = sevmap::Options::default()
+        .for_map::<char, i32, ()>()
+        .with_max_entries(2, EvictionPolicy::LeastRecentlyRead)
+        .construct::<()>();
+    w.insert('a', (), 1);
+    w.insert('b', (), 2);
+    w.publish();
+
+    assert_eq!(r.get(&'a').unwrap().last_read(), Some(1));
+    assert_eq!(r.coldest(1), vec!['b']);
+
+    // 'b' was never read, so it goes even though 'a' is older
+    w.insert('c', (), 3);
+    w.publish();
+    assert_eq!(w.take_evicted(), vec!['b']);
+    w.publish();
+    assert!(r.contains_key(&'a') && r.contains_key(&'c'));
+    assert_eq!(r.len(), 2);
+
+    // Without tracking there is nothing to report
+    let (mut w, r) = sevmap::new::<char, i32, (), ()>();
+    w.insert('a', (), 1);
+    w.publish();
+    assert_eq!(r.get(&'a').unwrap().last_read(), None);
+    assert!(r.hottest(1).is_empty());
+}
+
+#[test]
+fn rename() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (mut w, r) = sevmap::new::<char, i32, DropCounter, ()>();
+    w.insert('a', DropCounter(drops.clone()), 1);
+    w.insert('b', DropCounter(drops.clone()), 2);
+    w.publish();
+
+    // Moving onto a free key drops nothing
+    w.rename('a', 'c');
+    w.publish();
+    assert!(!r.contains_key(&'a'));
+    assert_eq!(r.get(&'c').unwrap().mut_v(), &1);
+    assert_eq!(drops.load(Ordering::SeqCst), 0);
+
+    // Moving onto an occupied key drops the value displaced, once
+    w.rename('c', 'b');
+    w.publish();
+    w.publish();
+    assert_eq!(r.len(), 1);
+    assert_eq!(r.get(&'b').unwrap().mut_v(), &1);
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+
+    // Moving a missing key leaves the target alone
+    w.rename('z', 'b');
+    w.publish();
+    assert_eq!(r.get(&'b').unwrap().mut_v(), &1);
+
+    // Transactions see the move before it is published
+    let moved = w.transaction(|tx| {
+        tx.rename('b', 'd');
+        Ok::<_, ()>((tx.contains_key(&'b'), tx.mut_v(&'d').copied()))
+    });
+    assert_eq!(moved, Ok((false, Some(1))));
+
+    drop(w);
+    drop(r);
+    assert_eq!(drops.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn rename_keeps_allocation() {
+    let (mut w, r) = sevmap::new::<char, i32, Box<u64>, ()>();
+    w.insert('a', Box::new(7), 1);
+    w.publish();
+    let before = &**r.get(&'a').unwrap().ref_v() as *const u64;
+
+    w.rename('a', 'b');
+    w.publish();
+    w.publish();
+    let after = &**r.get(&'b').unwrap().ref_v() as *const u64;
+    assert_eq!(before, after);
+}
+
+#[test]
+fn swap() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (mut w, r) = sevmap::new::<char, i32, DropCounter, ()>();
+    w.insert('a', DropCounter(drops.clone()), 1);
+    w.insert('b', DropCounter(drops.clone()), 2);
+    w.publish();
+
+    w.swap('a', 'b');
+    w.publish();
+    assert_eq!(r.get(&'a').unwrap().mut_v(), &2);
+    assert_eq!(r.get(&'b').unwrap().mut_v(), &1);
+
+    // With one key missing the value moves over, with equal keys nothing changes
+    w.swap('c', 'a');
+    w.swap('b', 'b');
+    w.publish();
+    assert!(!r.contains_key(&'a'));
+    assert_eq!(r.get(&'b').unwrap().mut_v(), &1);
+    assert_eq!(r.get(&'c').unwrap().mut_v(), &2);
+
+    let swapped = w.transaction(|tx| {
+        tx.swap('b', 'c');
+        tx.swap('d', 'e');
+        Ok::<_, ()>((
+            tx.mut_v(&'b').copied(),
+            tx.mut_v(&'c').copied(),
+            tx.contains_key(&'d'),
+        ))
+    });
+    assert_eq!(swapped, Ok((Some(2), Some(1), false)));
+    assert_eq!(r.get(&'b').unwrap().mut_v(), &2);
+
+    w.publish();
+    assert_eq!(drops.load(Ordering::SeqCst), 0);
+    drop(w);
+    drop(r);
+    assert_eq!(drops.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn remove_and_take() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (mut w, r) = sevmap::new::<char, Vec<u8>, DropCounter, ()>();
+    w.insert('a', DropCounter(drops.clone()), vec![1, 2]);
+    w.publish();
+
+    let taken = w.remove_and_take('a');
+    let missing = w.remove_and_take('b');
+    w.publish();
+    assert!(!r.contains_key(&'a'));
+
+    // Readers may still see the other copy until the next publish
+    assert!(!taken.is_resolved());
+    w.publish();
+    assert!(missing.take().unwrap().is_none());
+
+    let (ref_v, mut_v) = taken.take().unwrap().unwrap();
+    assert_eq!(mut_v, [1, 2]);
+    assert_eq!(drops.load(Ordering::SeqCst), 0);
+    drop(ref_v);
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+    assert!(taken.take().is_none());
+
+    // Dropping the write handle applies the removal to both copies
+    w.insert('c', DropCounter(drops.clone()), vec![3]);
+    w.publish();
+    let taken = w.remove_and_take('c');
+    drop(w);
+    assert_eq!(taken.take().unwrap().unwrap().1, [3]);
+    drop(r);
+    assert_eq!(drops.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+#[should_panic(expected = "savepoint is no longer valid")]
+fn savepoint_invalidated_by_earlier_rollback() {
+    let (mut w, _r) = sevmap::new::<u32, i32, (), ()>();
+
+    let sp1 = w.savepoint();
+    w.insert(1, (), 1);
+    w.insert(2, (), 2);
+    let sp2 = w.savepoint();
+    w.insert(3, (), 3);
+    w.rollback_to(sp1);
+    w.insert(4, (), 4);
+    w.insert(5, (), 5);
+    w.insert(6, (), 6);
+    w.rollback_to(sp2);
+}
+
+#[test]
+#[should_panic(expected = "savepoint is no longer valid")]
+fn savepoint_from_another_handle() {
+    let (mut w, _r) = sevmap::new::<u32, i32, (), ()>();
+    let (mut other, _other_r) = sevmap::new::<u32, i32, (), ()>();
+
+    // The savepoint refers to both pending operations and rollbacks that `w` does not have
+    other.insert(1, (), 1);
+    other.discard_pending();
+    other.insert(2, (), 2);
+    let sp = other.savepoint();
+    w.rollback_to(sp);
+}
+
+#[test]
+fn savepoint_survives_later_rollback() {
+    let (mut w, r) = sevmap::new::<u32, i32, (), ()>();
+
+    w.insert(1, (), 1);
+    let sp1 = w.savepoint();
+    w.insert(2, (), 2);
+    let sp2 = w.savepoint();
+    w.insert(3, (), 3);
+    w.rollback_to(sp2);
+    w.insert(4, (), 4);
+    // Rolling back to a savepoint taken before the last rollback point is still fine
+    w.rollback_to(sp1);
+    w.rollback_to(sp1);
+    w.publish();
+    assert_eq!(r.len(), 1);
+    assert!(r.contains_key(&1));
+}